@@ -0,0 +1,77 @@
+use egui::CentralPanel;
+use smithay_client_toolkit::reexports::calloop::EventLoop;
+use smithay_client_toolkit::reexports::calloop::ping::make_ping;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+use std::time::Duration;
+use wayapp::*;
+
+struct EguiApp {
+    counter: i32,
+}
+
+impl EguiApp {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        CentralPanel::default().show(ui, |ui| {
+            ui.heading("Egui WGPU / Smithay - Calloop");
+            ui.label(format!("Counter: {}", self.counter));
+            if ui.button("Increment").clicked() {
+                self.counter += 1;
+            }
+        });
+    }
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let mut event_loop = EventLoop::<Application>::try_new().expect("Failed to create loop");
+
+    // Emitted events and frame requests wake the loop with a ping
+    let (ping, ping_source) = make_ping().expect("Failed to create ping");
+    event_loop
+        .handle()
+        .insert_source(ping_source, |_, _, _| {})
+        .expect("Failed to insert ping");
+
+    let mut app = Application::new(move |_| ping.ping());
+    app.insert_into_loop(&event_loop.handle())
+        .expect("Failed to insert Wayland source");
+
+    event_loop
+        .handle()
+        .insert_timer(Duration::from_secs(1), |_| {
+            println!("Timer tick");
+            Some(Duration::from_secs(1))
+        })
+        .expect("Failed to insert timer");
+
+    let mut myapp = EguiApp { counter: 0 };
+
+    let window = app.xdg_shell.create_window(
+        app.compositor_state.create_surface(&app.qh),
+        WindowDecorations::ServerDefault,
+        &app.qh,
+    );
+    window.set_title("Calloop Example");
+    window.set_app_id("io.github.ciantic.wayapp.CalloopExample");
+    window.commit();
+
+    let mut window_app = EguiSurfaceState::new(&app, &window, 256, 256);
+
+    'main_loop: loop {
+        event_loop
+            .dispatch(None, &mut app)
+            .expect("Failed to dispatch");
+
+        let events = app.take_wayland_events();
+        window_app.handle_events(&mut app, &events, &mut |ui| myapp.ui(ui));
+
+        for event in events {
+            if let WaylandEvent::WindowRequestClose(_) = event {
+                break 'main_loop;
+            }
+        }
+    }
+}
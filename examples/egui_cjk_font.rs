@@ -0,0 +1,87 @@
+use egui::CentralPanel;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+use wayapp::*;
+
+// Common locations of Noto Sans CJK, pass another path as the first argument
+const FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+];
+
+struct EguiApp {
+    text: String,
+}
+
+impl EguiApp {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        CentralPanel::default().show(ui, |ui| {
+            ui.heading("日本語 / 中文 / 한국어");
+            ui.label("こんにちは世界、你好世界、안녕하세요 세계");
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Text input:");
+                ui.text_edit_singleline(&mut self.text);
+            });
+        });
+    }
+}
+
+enum AppEvent {
+    WaylandDispatch(DispatchToken),
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<AppEvent>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(AppEvent::WaylandDispatch(t));
+    });
+
+    // Fonts added to the application apply to all surfaces
+    let font = std::env::args()
+        .nth(1)
+        .into_iter()
+        .chain(FONT_PATHS.iter().map(|path| path.to_string()))
+        .find_map(|path| std::fs::read(path).ok());
+    match font {
+        Some(bytes) => app.add_egui_font("noto_sans_cjk", bytes, egui::FontFamily::Proportional),
+        None => eprintln!("CJK font not found, pass a path to a .ttf/.ttc as the first argument"),
+    }
+
+    let window = app.xdg_shell.create_window(
+        app.compositor_state.create_surface(&app.qh),
+        WindowDecorations::ServerDefault,
+        &app.qh,
+    );
+    window.set_title("CJK Font Example");
+    window.set_app_id("io.github.ciantic.wayapp.CjkFont");
+    window.commit();
+
+    let mut myapp = EguiApp {
+        text: "漢字".into(),
+    };
+    let mut egui_window = EguiSurfaceState::new(&app, &window, 400, 200);
+
+    app.run_dispatcher();
+
+    'main_loop: loop {
+        if let Ok(event) = rx.recv() {
+            match event {
+                AppEvent::WaylandDispatch(token) => {
+                    let events = app.dispatch_pending(token);
+                    egui_window.handle_events(&mut app, &events, &mut |ui| myapp.ui(ui));
+
+                    for event in events {
+                        if let WaylandEvent::WindowRequestClose(_) = event {
+                            break 'main_loop;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
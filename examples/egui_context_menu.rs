@@ -0,0 +1,115 @@
+use egui::CentralPanel;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::popup::Popup;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+use wayapp::*;
+
+struct EguiApp {
+    last_action: String,
+    open_menu_at: Option<egui::Pos2>,
+    menu_position: Option<(i32, i32)>,
+}
+
+impl EguiApp {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        CentralPanel::default().show(ui, |ui| {
+            ui.heading("Right click anywhere");
+            ui.label(format!("Last action: {}", self.last_action));
+            // Near the right or bottom edge of the screen the compositor
+            // flips the menu to the other side of the pointer
+            ui.label("Right click near a screen edge, the menu stays visible");
+            if let Some((x, y)) = self.menu_position {
+                ui.label(format!("Menu placed at: {x}, {y}"));
+            }
+
+            let response = ui.interact(ui.max_rect(), ui.id().with("area"), egui::Sense::click());
+            if response.secondary_clicked() {
+                self.open_menu_at = response.interact_pointer_pos();
+            }
+        });
+    }
+}
+
+struct ContextMenu {
+    chosen: Option<&'static str>,
+}
+
+impl ContextMenu {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        CentralPanel::default().show(ui, |ui| {
+            for action in ["Cut", "Copy", "Paste"] {
+                if ui.button(action).clicked() {
+                    self.chosen = Some(action);
+                }
+            }
+        });
+    }
+}
+
+enum AppEvent {
+    WaylandDispatch(DispatchToken),
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<AppEvent>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(AppEvent::WaylandDispatch(t));
+    });
+
+    let window = app.xdg_shell.create_window(
+        app.compositor_state.create_surface(&app.qh),
+        WindowDecorations::ServerDefault,
+        &app.qh,
+    );
+    window.set_title("Context Menu Example");
+    window.set_app_id("io.github.ciantic.wayapp.ContextMenu");
+    window.commit();
+
+    let mut myapp = EguiApp {
+        last_action: "none".into(),
+        open_menu_at: None,
+        menu_position: None,
+    };
+    let mut menu = ContextMenu { chosen: None };
+    let mut egui_window = EguiSurfaceState::new(&app, &window, 400, 300);
+    let mut egui_menu: Option<EguiSurfaceState<Popup>> = None;
+
+    app.run_dispatcher();
+
+    'main_loop: loop {
+        if let Ok(event) = rx.recv() {
+            match event {
+                AppEvent::WaylandDispatch(token) => {
+                    let events = app.dispatch_pending(token);
+                    egui_window.handle_events(&mut app, &events, &mut |ui| myapp.ui(ui));
+                    // Dropped automatically when dismissed by clicking outside
+                    egui_menu.handle_events(&mut app, &events, &mut |ui| menu.ui(ui));
+                    let menu_position = egui_menu.as_ref().and_then(|m| m.popup_position());
+                    if menu_position.is_some() && menu_position != myapp.menu_position {
+                        myapp.menu_position = menu_position;
+                        egui_window.request_frame();
+                    }
+
+                    if let Some(pos) = myapp.open_menu_at.take() {
+                        egui_menu =
+                            egui_window.open_popup_at(&app, pos, LogicalSize::new(120, 100));
+                    }
+                    if let Some(action) = menu.chosen.take() {
+                        myapp.last_action = action.into();
+                        egui_menu = None;
+                        egui_window.request_frame();
+                    }
+
+                    for event in events {
+                        if let WaylandEvent::WindowRequestClose(_) = event {
+                            break 'main_loop;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
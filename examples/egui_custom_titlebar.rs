@@ -0,0 +1,116 @@
+use egui::CentralPanel;
+use egui::Sense;
+use smithay_client_toolkit::reexports::csd_frame::WindowManagerCapabilities;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+use wayapp::*;
+use wayland_protocols::xdg::shell::client::xdg_toplevel::ResizeEdge;
+
+const RESIZE_HANDLE: f32 = 8.0;
+
+struct EguiApp {
+    title_bar_action: Option<TitleBarAction>,
+    start_resize: Option<ResizeEdge>,
+    capabilities: WindowManagerCapabilities,
+    maximized: bool,
+}
+
+impl EguiApp {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        CentralPanel::default().show(ui, |ui| {
+            let rect = ui.max_rect();
+
+            // Dragging the title bar moves the window, buttons the compositor
+            // doesn't support are grayed out
+            let title_bar = TitleBar::new("Custom title bar, drag me")
+                .capabilities(self.capabilities)
+                .maximized(self.maximized)
+                .show(ui);
+            if title_bar.action.is_some() {
+                self.title_bar_action = title_bar.action;
+            }
+
+            // Bottom right corner resizes the window
+            let corner = egui::Rect::from_min_max(
+                rect.max - egui::vec2(RESIZE_HANDLE, RESIZE_HANDLE),
+                rect.max,
+            );
+            let corner = ui
+                .interact(corner, ui.id().with("resize_corner"), Sense::drag())
+                .on_hover_cursor(egui::CursorIcon::ResizeSouthEast);
+            if corner.drag_started() {
+                self.start_resize = Some(ResizeEdge::BottomRight);
+            }
+
+            let content = egui::UiBuilder::new().max_rect(title_bar.content_rect.shrink(4.0));
+            ui.scope_builder(content, |ui| {
+                ui.label("Borderless window with client side title bar");
+            });
+        });
+    }
+}
+
+enum AppEvent {
+    WaylandDispatch(DispatchToken),
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<AppEvent>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(AppEvent::WaylandDispatch(t));
+    });
+
+    let window = app.xdg_shell.create_window(
+        app.compositor_state.create_surface(&app.qh),
+        WindowDecorations::RequestClient,
+        &app.qh,
+    );
+    window.set_title("Custom Title Bar");
+    window.set_app_id("io.github.ciantic.wayapp.CustomTitleBar");
+    window.commit();
+
+    let mut myapp = EguiApp {
+        title_bar_action: None,
+        start_resize: None,
+        capabilities: WindowManagerCapabilities::all(),
+        maximized: false,
+    };
+    let mut egui_window = EguiSurfaceState::new(&app, &window, 400, 300);
+
+    app.run_dispatcher();
+
+    'main_loop: loop {
+        if let Ok(event) = rx.recv() {
+            match event {
+                AppEvent::WaylandDispatch(token) => {
+                    let events = app.dispatch_pending(token);
+                    egui_window.handle_events(&mut app, &events, &mut |ui| myapp.ui(ui));
+                    myapp.capabilities = egui_window.capabilities();
+                    myapp.maximized = egui_window.is_maximized();
+
+                    match myapp.title_bar_action.take() {
+                        Some(TitleBarAction::Close) => break 'main_loop,
+                        Some(action) => egui_window.apply_title_bar_action(&app, action),
+                        None => {}
+                    }
+
+                    // Interactive resize needs the serial of the button press
+                    // that started the drag
+                    if let Some(serial) = app.last_pointer_serial()
+                        && let Some(edge) = myapp.start_resize.take()
+                    {
+                        egui_window.start_interactive_resize(&app, serial, edge);
+                    }
+                    for event in events {
+                        if let WaylandEvent::WindowRequestClose(_) = event {
+                            break 'main_loop;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use egui::CentralPanel;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::Window;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+use wayapp::*;
+
+#[derive(Default)]
+struct MainApp {
+    open_dialog: bool,
+    file_name: String,
+}
+
+impl MainApp {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        CentralPanel::default().show(ui, |ui| {
+            ui.heading("Main window");
+            if ui.button("Save as...").clicked() {
+                self.open_dialog = true;
+            }
+            if !self.file_name.is_empty() {
+                ui.label(format!("Saved as: {}", self.file_name));
+            }
+        });
+    }
+}
+
+#[derive(Default)]
+struct DialogApp {
+    file_name: String,
+    done: bool,
+}
+
+impl DialogApp {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        CentralPanel::default().show(ui, |ui| {
+            ui.heading("Save as");
+            ui.text_edit_singleline(&mut self.file_name);
+            if ui.button("Save").clicked() {
+                self.done = true;
+            }
+        });
+    }
+}
+
+fn create_window(app: &Application, title: &str) -> Window {
+    let window = app.xdg_shell.create_window(
+        app.compositor_state.create_surface(&app.qh),
+        WindowDecorations::ServerDefault,
+        &app.qh,
+    );
+    window.set_title(title);
+    window.set_app_id("io.github.ciantic.wayapp.DialogExample");
+    window
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<DispatchToken>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(t);
+    });
+
+    let main_window = create_window(&app, "Dialog Example");
+    main_window.commit();
+    let mut main_window_app = EguiSurfaceState::new(&app, main_window, 400, 300);
+    let mut main_app = MainApp::default();
+
+    let mut dialog: Option<(EguiSurfaceState<Window>, DialogApp)> = None;
+
+    app.run_dispatcher();
+
+    'main_loop: while let Ok(token) = rx.recv() {
+        let events = app.dispatch_pending(token);
+        main_window_app.handle_events(&mut app, &events, &mut |ui| main_app.ui(ui));
+
+        if let Some((dialog_window_app, dialog_app)) = &mut dialog {
+            dialog_window_app.handle_events(&mut app, &events, &mut |ui| dialog_app.ui(ui));
+            if dialog_app.done {
+                main_app.file_name = dialog_app.file_name.clone();
+                dialog = None;
+            }
+        }
+
+        // Parent must be set before the first commit of the dialog
+        if main_app.open_dialog {
+            main_app.open_dialog = false;
+            if dialog.is_none() {
+                let mut dialog_window_app =
+                    EguiSurfaceState::new(&app, create_window(&app, "Save as"), 300, 120);
+                dialog_window_app.set_parent(Some(&main_window_app));
+                dialog_window_app.set_modal(&app, true);
+                dialog_window_app.commit();
+                dialog = Some((dialog_window_app, DialogApp::default()));
+            }
+        }
+
+        for event in events {
+            if let WaylandEvent::WindowRequestClose(window) = event {
+                if window == *main_window_app {
+                    break 'main_loop;
+                }
+                dialog = None;
+            }
+        }
+    }
+}
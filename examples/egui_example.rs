@@ -151,6 +151,10 @@ fn main() {
                     example_window_app.handle_events(&mut app, &events, &mut |ui| myapp1.ui(ui));
                     layer_surface_app.handle_events(&mut app, &events, &mut |ui| myapp2.ui(ui));
 
+                    // Title reflects the app state, only sent when it changes
+                    example_window_app
+                        .update_title(&format!("Example Window — Counter {}", myapp1.counter));
+
                     // Update FPS info
                     if let Some(last_render) = example_window_app.get_frame_timings() {
                         myapp1.set_last_render(last_render);
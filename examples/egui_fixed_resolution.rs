@@ -0,0 +1,68 @@
+use egui::CentralPanel;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+use wayapp::*;
+
+struct EguiApp {
+    counter: i32,
+}
+
+impl EguiApp {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        CentralPanel::default().show(ui, |ui| {
+            ui.heading("Designed at 800×600");
+            ui.label(format!("Available rect: {:?}", ui.max_rect()));
+            ui.label(format!("Counter: {}", self.counter));
+            if ui.button("Increment").clicked() {
+                self.counter += 1;
+            }
+        });
+    }
+}
+
+enum AppEvent {
+    WaylandDispatch(DispatchToken),
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<AppEvent>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(AppEvent::WaylandDispatch(t));
+    });
+
+    let window = app.xdg_shell.create_window(
+        app.compositor_state.create_surface(&app.qh),
+        WindowDecorations::ServerDefault,
+        &app.qh,
+    );
+    window.set_title("Fixed Resolution Example");
+    window.set_app_id("io.github.ciantic.wayapp.FixedResolution");
+    window.commit();
+
+    let mut myapp = EguiApp { counter: 0 };
+    let mut egui_window = EguiSurfaceState::new(&app, &window, 400, 300);
+
+    // UI is laid out at 800×600 and scaled to whatever size the window is
+    egui_window.set_logical_resolution(Some(LogicalSize::new(800, 600)));
+
+    app.run_dispatcher();
+
+    'main_loop: loop {
+        if let Ok(event) = rx.recv() {
+            match event {
+                AppEvent::WaylandDispatch(token) => {
+                    let events = app.dispatch_pending(token);
+                    egui_window.handle_events(&mut app, &events, &mut |ui| myapp.ui(ui));
+                    for event in events {
+                        if let WaylandEvent::WindowRequestClose(_) = event {
+                            break 'main_loop;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use egui::CentralPanel;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+use wayapp::*;
+use wayland_client::Connection;
+use wayland_client::Dispatch;
+use wayland_client::QueueHandle;
+use wayland_protocols::wp::idle_inhibit::zv1::client::zwp_idle_inhibit_manager_v1;
+use wayland_protocols::wp::idle_inhibit::zv1::client::zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1;
+use wayland_protocols::wp::idle_inhibit::zv1::client::zwp_idle_inhibitor_v1;
+use wayland_protocols::wp::idle_inhibit::zv1::client::zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1;
+
+// wayapp has `EguiSurfaceState::inhibit_idle` for this, the example shows how
+// to bind a protocol yourself with `Application::globals`
+
+/// User data of the idle inhibit objects
+///
+/// `Dispatch` is implemented for `Application` with this local type, which the
+/// orphan rules allow in this crate.
+struct IdleInhibit;
+
+impl Dispatch<ZwpIdleInhibitManagerV1, IdleInhibit> for Application {
+    fn event(
+        _: &mut Self,
+        _: &ZwpIdleInhibitManagerV1,
+        _: zwp_idle_inhibit_manager_v1::Event,
+        _: &IdleInhibit,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // No events
+    }
+}
+
+impl Dispatch<ZwpIdleInhibitorV1, IdleInhibit> for Application {
+    fn event(
+        _: &mut Self,
+        _: &ZwpIdleInhibitorV1,
+        _: zwp_idle_inhibitor_v1::Event,
+        _: &IdleInhibit,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // No events
+    }
+}
+
+struct EguiApp {
+    keep_awake: bool,
+    available: bool,
+}
+
+impl EguiApp {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        CentralPanel::default().show(ui, |ui| {
+            ui.heading("Pretend this is a video");
+            ui.add_enabled(
+                self.available,
+                egui::Checkbox::new(&mut self.keep_awake, "Keep the screen awake"),
+            );
+            if !self.available {
+                ui.label("The compositor doesn't support idle-inhibit");
+            }
+        });
+    }
+}
+
+enum AppEvent {
+    WaylandDispatch(DispatchToken),
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<AppEvent>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(AppEvent::WaylandDispatch(t));
+    });
+
+    // Bind a protocol wayapp doesn't wrap
+    let idle_inhibit_manager: Option<ZwpIdleInhibitManagerV1> =
+        app.globals().bind(&app.qh, 1..=1, IdleInhibit).ok();
+
+    let window = app.xdg_shell.create_window(
+        app.compositor_state.create_surface(&app.qh),
+        WindowDecorations::ServerDefault,
+        &app.qh,
+    );
+    window.set_title("Idle Inhibit Example");
+    window.set_app_id("io.github.ciantic.wayapp.IdleInhibit");
+    window.commit();
+
+    let mut myapp = EguiApp {
+        keep_awake: true,
+        available: idle_inhibit_manager.is_some(),
+    };
+    let mut egui_window = EguiSurfaceState::new(&app, &window, 300, 120);
+    let mut inhibitor: Option<ZwpIdleInhibitorV1> = None;
+
+    app.run_dispatcher();
+
+    'main_loop: loop {
+        if let Ok(event) = rx.recv() {
+            match event {
+                AppEvent::WaylandDispatch(token) => {
+                    let events = app.dispatch_pending(token);
+                    egui_window.handle_events(&mut app, &events, &mut |ui| myapp.ui(ui));
+
+                    // The screen stays awake while the surface is visible and
+                    // the inhibitor exists
+                    if let Some(manager) = &idle_inhibit_manager {
+                        if myapp.keep_awake && inhibitor.is_none() {
+                            inhibitor = Some(manager.create_inhibitor(
+                                window.wl_surface(),
+                                &app.qh,
+                                IdleInhibit,
+                            ));
+                        } else if !myapp.keep_awake
+                            && let Some(inhibitor) = inhibitor.take()
+                        {
+                            inhibitor.destroy();
+                        }
+                    }
+
+                    for event in events {
+                        if let WaylandEvent::WindowRequestClose(_) = event {
+                            break 'main_loop;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use egui::CentralPanel;
+use egui::Id;
+use egui::Key;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::wlr_layer::Layer;
+use wayapp::*;
+
+struct Launcher {
+    query: String,
+    focused: bool,
+    done: bool,
+}
+
+impl Launcher {
+    fn input_id() -> Id {
+        Id::new("launcher_query")
+    }
+
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        CentralPanel::default().show(ui, |ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .id(Self::input_id())
+                    .hint_text("Type to search...")
+                    .desired_width(f32::INFINITY),
+            );
+            if !self.focused {
+                ui.weak("No keyboard focus, the compositor didn't grant exclusive input");
+            }
+            if ui.input(|i| i.key_pressed(Key::Enter)) {
+                println!("{}", self.query);
+                self.done = true;
+            }
+            if ui.input(|i| i.key_pressed(Key::Escape)) {
+                self.done = true;
+            }
+        });
+    }
+}
+
+enum AppEvent {
+    WaylandDispatch(DispatchToken),
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<AppEvent>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(AppEvent::WaylandDispatch(t));
+    });
+
+    let layer_surface = app.layer_shell.create_layer_surface(
+        &app.qh,
+        app.compositor_state.create_surface(&app.qh),
+        Layer::Overlay,
+        Some("ExampleLauncher"),
+        None,
+    );
+    layer_surface.set_size(400, 80);
+    layer_surface.commit();
+
+    let mut launcher = Launcher {
+        query: String::new(),
+        focused: false,
+        done: false,
+    };
+    let mut egui_surface = EguiSurfaceState::new(&app, &layer_surface, 400, 80);
+
+    // All typing goes to the launcher, like dmenu
+    egui_surface.set_exclusive_keyboard(true);
+
+    // Focus the text field as soon as the launcher gets the keyboard, so it's
+    // ready to type the instant it opens
+    egui_surface.set_on_keyboard_enter(|ctx| {
+        ctx.memory_mut(|m| m.request_focus(Launcher::input_id()));
+    });
+
+    app.run_dispatcher();
+
+    while !launcher.done {
+        if let Ok(event) = rx.recv() {
+            match event {
+                AppEvent::WaylandDispatch(token) => {
+                    let events = app.dispatch_pending(token);
+                    egui_surface.handle_events(&mut app, &events, &mut |ui| launcher.ui(ui));
+                    launcher.focused = egui_surface.has_keyboard_focus();
+                }
+            }
+        }
+    }
+}
@@ -19,6 +19,8 @@ struct EguiApp {
     anchor_bottom: bool,
     anchor_left: bool,
     anchor_right: bool,
+    layer: Layer,
+    requested_layer: Option<Layer>,
 }
 
 impl EguiApp {
@@ -35,6 +37,8 @@ impl EguiApp {
             anchor_bottom: false,
             anchor_left: false,
             anchor_right: false,
+            layer: Layer::Top,
+            requested_layer: None,
         }
     }
 
@@ -63,6 +67,24 @@ impl EguiApp {
 
             ui.separator();
 
+            // Layer section
+            ui.heading("Layer");
+            ui.horizontal(|ui| {
+                for (layer, name) in [
+                    (Layer::Background, "Background"),
+                    (Layer::Bottom, "Bottom"),
+                    (Layer::Top, "Top"),
+                    (Layer::Overlay, "Overlay"),
+                ] {
+                    if ui.radio(self.layer == layer, name).clicked() {
+                        self.layer = layer;
+                        self.requested_layer = Some(layer);
+                    }
+                }
+            });
+
+            ui.separator();
+
             // Anchor section
             ui.heading("Anchor");
             ui.horizontal(|ui| {
@@ -166,6 +188,9 @@ fn main() {
                 AppEvent::WaylandDispatch(token) => {
                     let events = app.dispatch_pending(token);
                     egui_surface.handle_events(&mut app, &events, &mut |ui| my_app.ui(ui));
+                    if let Some(layer) = my_app.requested_layer.take() {
+                        egui_surface.set_layer(layer);
+                    }
                 } // Handle other events here
             }
         }
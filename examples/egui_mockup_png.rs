@@ -0,0 +1,26 @@
+use egui::CentralPanel;
+use wayapp::*;
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "mockup.png".into());
+
+    // No Wayland connection is needed, the UI is rendered offscreen
+    render_ui_to_png(&path, LogicalSize::new(400, 300), Scale::new(2.0), |ui| {
+        CentralPanel::default().show(ui, |ui| {
+            ui.heading("UI mockup");
+            ui.label("Rendered offscreen to a PNG file");
+            ui.separator();
+            let _ = ui.button("A button");
+            ui.checkbox(&mut true, "A checkbox");
+            ui.add(egui::Slider::new(&mut 42.0, 0.0..=100.0).text("A slider"));
+        });
+    })
+    .expect("Failed to render mockup");
+
+    println!("Wrote {}", path);
+}
@@ -0,0 +1,68 @@
+use egui::CentralPanel;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+use wayapp::*;
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<DispatchToken>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(t);
+    });
+
+    let window = app.xdg_shell.create_window(
+        app.compositor_state.create_surface(&app.qh),
+        WindowDecorations::ServerDefault,
+        &app.qh,
+    );
+    window.set_title("Notifications");
+    window.set_app_id("io.github.ciantic.wayapp.NotificationsExample");
+    window.commit();
+    let mut window_app = EguiSurfaceState::new(&app, window, 300, 120);
+
+    let mut notifications = NotificationStack::default();
+    let mut pushed = 0;
+    let mut notify = false;
+
+    app.run_dispatcher();
+
+    'main_loop: while let Ok(token) = rx.recv() {
+        let events = app.dispatch_pending(token);
+        window_app.handle_events(&mut app, &events, &mut |ui| {
+            CentralPanel::default().show(ui, |ui| {
+                ui.heading("Notifications");
+                notify = ui.button("Notify").clicked();
+            });
+        });
+
+        if notify {
+            pushed += 1;
+            let number = pushed;
+            let result = notifications.push(&app, LogicalSize::new(280, 80), move |ui| {
+                let mut open = true;
+                CentralPanel::default().show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.heading(format!("Notification {number}"));
+                        open = !ui.button("✖").clicked();
+                    });
+                    ui.label("Dismissed in 5 seconds");
+                });
+                open
+            });
+            if let Err(err) = result {
+                eprintln!("Notification not shown: {err}");
+            }
+        }
+        notifications.handle_events(&mut app, &events);
+
+        for event in events {
+            if let WaylandEvent::WindowRequestClose(window) = event {
+                if window == *window_app {
+                    break 'main_loop;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use egui::CentralPanel;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::wlr_layer::Anchor;
+use smithay_client_toolkit::shell::wlr_layer::Layer;
+use smithay_client_toolkit::shell::wlr_layer::LayerSurface;
+use wayapp::*;
+
+const BAR_HEIGHT: u32 = 32;
+
+enum AppEvent {
+    WaylandDispatch(DispatchToken),
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<AppEvent>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(AppEvent::WaylandDispatch(t));
+    });
+
+    // One bar per monitor, bars are created and dropped on hotplug
+    let mut bars = PerOutputSurfaces::new(&app, |app, output| {
+        let layer_surface = app.create_layer_surface_on(output, Layer::Top, Some("ExampleBar"));
+        layer_surface.set_anchor(Anchor::TOP | Anchor::LEFT | Anchor::RIGHT);
+        layer_surface.set_size(0, BAR_HEIGHT);
+        layer_surface.set_exclusive_zone(BAR_HEIGHT as i32);
+        layer_surface.commit();
+        EguiSurfaceState::<LayerSurface>::new(app, layer_surface, 256, BAR_HEIGHT)
+    });
+
+    app.run_dispatcher();
+
+    loop {
+        if let Ok(event) = rx.recv() {
+            match event {
+                AppEvent::WaylandDispatch(token) => {
+                    let events = app.dispatch_pending(token);
+                    bars.handle_events(&app, &events);
+                    for (_, bar) in bars.iter_mut() {
+                        let name = bar.name().to_string();
+                        bar.handle_events(&mut app, &events, &mut |ui| {
+                            CentralPanel::default().show(ui, |ui| {
+                                ui.label(format!("Bar on {}", name));
+                            });
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
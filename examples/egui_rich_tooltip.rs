@@ -0,0 +1,79 @@
+use egui::CentralPanel;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::wlr_layer::Anchor;
+use smithay_client_toolkit::shell::wlr_layer::Layer;
+use wayapp::*;
+
+const BAR_HEIGHT: u32 = 32;
+
+enum AppEvent {
+    WaylandDispatch(DispatchToken),
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<AppEvent>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(AppEvent::WaylandDispatch(t));
+    });
+
+    // Bar at the top left of the output, so it's at (0, 0)
+    let layer_surface = app.layer_shell.create_layer_surface(
+        &app.qh,
+        app.compositor_state.create_surface(&app.qh),
+        Layer::Top,
+        Some("TooltipBar"),
+        None,
+    );
+    layer_surface.set_anchor(Anchor::TOP | Anchor::LEFT | Anchor::RIGHT);
+    layer_surface.set_size(0, BAR_HEIGHT);
+    layer_surface.set_exclusive_zone(BAR_HEIGHT as i32);
+    layer_surface.commit();
+    let mut bar = EguiSurfaceState::new(&app, &layer_surface, 256, BAR_HEIGHT);
+
+    let mut tooltip: Option<PointerTooltip> = None;
+    let mut hovered = false;
+
+    app.run_dispatcher();
+
+    while let Ok(AppEvent::WaylandDispatch(token)) = rx.recv() {
+        let events = app.dispatch_pending(token);
+        bar.handle_events(&mut app, &events, &mut |ui| {
+            CentralPanel::default().show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Bar");
+                    hovered = ui.label("🛈 Hover me").hovered();
+                });
+            });
+        });
+
+        // The tooltip is taller than the bar, EGUI's own tooltips would be
+        // clipped to it
+        if hovered
+            && tooltip
+                .as_ref()
+                .is_none_or(|tooltip| tooltip.is_dismissed())
+        {
+            tooltip = Some(
+                app.create_pointer_tooltip(bar.wl_surface(), (0, 0), LogicalSize::new(280, 180))
+                    .expect("Failed to create tooltip"),
+            );
+        } else if !hovered && let Some(tooltip) = &mut tooltip {
+            tooltip.dismiss();
+        }
+
+        if let Some(tooltip) = &mut tooltip {
+            tooltip.handle_events(&mut app, &events, &mut |ui| {
+                CentralPanel::default().show(ui, |ui| {
+                    ui.heading("Rich tooltip");
+                    ui.label("A separate overlay surface following the pointer.");
+                    ui.separator();
+                    ui.add(egui::ProgressBar::new(0.6).text("60 %"));
+                    ui.hyperlink("https://github.com/Ciantic/wayapp");
+                });
+            });
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use wayapp::*;
+
+fn main() {
+    let mut counter = 0;
+    Application::run_egui_window("Simple", LogicalSize::new(300, 120), |ui| {
+        egui::CentralPanel::default().show(ui, |ui| {
+            ui.heading("Hello from EGUI!");
+            if ui.button(format!("Clicked {counter} times")).clicked() {
+                counter += 1;
+                // Shown in the taskbar
+                let title = format!("Simple ({counter})");
+                ui.ctx()
+                    .send_viewport_cmd(egui::ViewportCommand::Title(title));
+            }
+        });
+    });
+}
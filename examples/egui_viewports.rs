@@ -0,0 +1,70 @@
+use egui::CentralPanel;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use wayapp::*;
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<DispatchToken>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(t);
+    });
+
+    let window = app.xdg_shell.create_window(
+        app.compositor_state.create_surface(&app.qh),
+        WindowDecorations::ServerDefault,
+        &app.qh,
+    );
+    window.set_title("Viewports");
+    window.set_app_id("io.github.ciantic.wayapp.ViewportsExample");
+    window.commit();
+    let mut window_app = EguiSurfaceState::new(&app, window, 300, 120);
+
+    // Deferred viewports run in their own passes, so their state is shared
+    let show_child = Arc::new(AtomicBool::new(false));
+
+    app.run_dispatcher();
+
+    'main_loop: while let Ok(token) = rx.recv() {
+        let events = app.dispatch_pending(token);
+        window_app.handle_events(&mut app, &events, &mut |ui| {
+            CentralPanel::default().show(ui, |ui| {
+                ui.heading("Viewports");
+                let mut show = show_child.load(Ordering::Relaxed);
+                ui.checkbox(&mut show, "Show child window");
+                show_child.store(show, Ordering::Relaxed);
+            });
+
+            if show_child.load(Ordering::Relaxed) {
+                let show_child = show_child.clone();
+                ui.ctx().show_viewport_deferred(
+                    egui::ViewportId::from_hash_of("child"),
+                    egui::ViewportBuilder::default()
+                        .with_title("Child")
+                        .with_inner_size([250.0, 100.0]),
+                    move |ui, _class| {
+                        CentralPanel::default().show(ui, |ui| {
+                            ui.label("A deferred viewport in its own window");
+                        });
+                        if ui.input(|i| i.viewport().close_requested()) {
+                            show_child.store(false, Ordering::Relaxed);
+                        }
+                    },
+                );
+            }
+        });
+
+        for event in events {
+            if let WaylandEvent::WindowRequestClose(window) = event {
+                if window == *window_app {
+                    break 'main_loop;
+                }
+            }
+        }
+    }
+}
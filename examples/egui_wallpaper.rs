@@ -0,0 +1,72 @@
+use egui::Color32;
+use smithay_client_toolkit::shell::wlr_layer::Layer;
+use wayapp::*;
+use wayland_client::Proxy;
+
+struct Wallpaper {
+    name: String,
+}
+
+impl Wallpaper {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        let rect = ui.max_rect();
+        let top = Color32::from_rgb(30, 60, 120);
+        let bottom = Color32::from_rgb(200, 90, 60);
+
+        // Vertical gradient with vertex colors
+        let mut mesh = egui::Mesh::default();
+        mesh.colored_vertex(rect.left_top(), top);
+        mesh.colored_vertex(rect.right_top(), top);
+        mesh.colored_vertex(rect.left_bottom(), bottom);
+        mesh.colored_vertex(rect.right_bottom(), bottom);
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(1, 3, 2);
+        ui.painter().add(mesh);
+
+        ui.painter().text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            &self.name,
+            egui::FontId::proportional(32.0),
+            Color32::WHITE,
+        );
+    }
+}
+
+enum AppEvent {
+    WaylandDispatch(DispatchToken),
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<AppEvent>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(AppEvent::WaylandDispatch(t));
+    });
+
+    // One wallpaper per monitor, created and dropped on hotplug
+    let mut wallpapers =
+        app.create_egui_layer_per_output(Layer::Background, "ExampleWallpaper", |output| {
+            Wallpaper {
+                name: format!("Output {:?}", output.id()),
+            }
+        });
+
+    app.run_dispatcher();
+
+    loop {
+        if let Ok(event) = rx.recv() {
+            match event {
+                AppEvent::WaylandDispatch(token) => {
+                    let events = app.dispatch_pending(token);
+                    wallpapers.handle_events(&app, &events);
+                    for (_, (surface, wallpaper)) in wallpapers.iter_mut() {
+                        surface.handle_events(&mut app, &events, &mut |ui| wallpaper.ui(ui));
+                    }
+                }
+            }
+        }
+    }
+}
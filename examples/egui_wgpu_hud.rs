@@ -0,0 +1,216 @@
+use egui::CentralPanel;
+use egui_wgpu::CallbackResources;
+use egui_wgpu::CallbackTrait;
+use egui_wgpu::ScreenDescriptor;
+use egui_wgpu::wgpu;
+use egui_wgpu::wgpu::util::StagingBelt;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+use wayapp::*;
+
+const SHADER: &str = r#"
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let x = f32(i32(index) - 1);
+    let y = f32(i32(index & 1u) * 2 - 1);
+    return vec4<f32>(x * 0.8, y * 0.8, 0.0, 1.0);
+}
+
+@group(0) @binding(0)
+var<uniform> color: vec4<f32>;
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return color;
+}
+"#;
+
+/// Draws a triangle with WGPU in the EGUI render pass
+struct Triangle {
+    format: wgpu::TextureFormat,
+    color: [f32; 4],
+}
+
+/// Pipeline and color uniform of the triangle, kept in the callback resources
+struct TriangleResources {
+    pipeline: wgpu::RenderPipeline,
+    uniform: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl CallbackTrait for Triangle {
+    fn prepare(
+        &self,
+        device: &wgpu::Device,
+        _queue: &wgpu::Queue,
+        _screen_descriptor: &ScreenDescriptor,
+        egui_encoder: &mut wgpu::CommandEncoder,
+        resources: &mut CallbackResources,
+    ) -> Vec<wgpu::CommandBuffer> {
+        if !resources.contains::<TriangleResources>() {
+            resources.insert(create_resources(device, self.format));
+        }
+        // Upload the color of this frame through the renderer's staging belt
+        let uniform = resources
+            .get::<TriangleResources>()
+            .unwrap()
+            .uniform
+            .clone();
+        if let Some(staging_belt) = resources.get_mut::<StagingBelt>() {
+            let bytes: Vec<u8> = self.color.iter().flat_map(|c| c.to_ne_bytes()).collect();
+            let size = wgpu::BufferSize::new(bytes.len() as u64).unwrap();
+            staging_belt
+                .write_buffer(egui_encoder, &uniform, 0, size)
+                .copy_from_slice(&bytes);
+        }
+        Vec::new()
+    }
+
+    fn paint(
+        &self,
+        _info: egui::PaintCallbackInfo,
+        render_pass: &mut wgpu::RenderPass<'static>,
+        resources: &CallbackResources,
+    ) {
+        if let Some(triangle) = resources.get::<TriangleResources>() {
+            render_pass.set_pipeline(&triangle.pipeline);
+            render_pass.set_bind_group(0, &triangle.bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+    }
+}
+
+fn create_resources(device: &wgpu::Device, format: wgpu::TextureFormat) -> TriangleResources {
+    let pipeline = create_pipeline(device, format);
+    let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("triangle color"),
+        size: 16,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("triangle color"),
+        layout: &pipeline.get_bind_group_layout(0),
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: uniform.as_entire_binding(),
+        }],
+    });
+    TriangleResources {
+        pipeline,
+        uniform,
+        bind_group,
+    }
+}
+
+fn create_pipeline(device: &wgpu::Device, format: wgpu::TextureFormat) -> wgpu::RenderPipeline {
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("triangle"),
+        source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("triangle"),
+        layout: None,
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[],
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: Some("fs_main"),
+            compilation_options: Default::default(),
+            targets: &[Some(format.into())],
+        }),
+        multiview_mask: None,
+        cache: None,
+    })
+}
+
+struct EguiApp {
+    format: wgpu::TextureFormat,
+    hud_visible: bool,
+}
+
+impl EguiApp {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        CentralPanel::default()
+            .frame(egui::Frame::NONE)
+            .show(ui, |ui| {
+                // Scene under everything else, painted in the central panel
+                let rect = ui.max_rect();
+                // Pulsing color, uploaded each frame
+                let time = ui.input(|i| i.time) as f32;
+                let pulse = 0.5 + 0.5 * (time * 2.0).sin();
+                ui.painter().add(egui_wgpu::Callback::new_paint_callback(
+                    rect,
+                    Triangle {
+                        format: self.format,
+                        color: [0.9, 0.4 * pulse, 0.1, 1.0],
+                    },
+                ));
+                ui.ctx().request_repaint();
+            });
+
+        // HUD over the scene, EGUI orders windows and tooltips above it
+        egui::Window::new("HUD")
+            .open(&mut self.hud_visible)
+            .show(ui.ctx(), |ui| {
+                ui.label("EGUI above custom WGPU content");
+                ui.label("Hover me")
+                    .on_hover_text("Tooltips are drawn on top");
+            });
+    }
+}
+
+enum AppEvent {
+    WaylandDispatch(DispatchToken),
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<AppEvent>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(AppEvent::WaylandDispatch(t));
+    });
+
+    let window = app.xdg_shell.create_window(
+        app.compositor_state.create_surface(&app.qh),
+        WindowDecorations::ServerDefault,
+        &app.qh,
+    );
+    window.set_title("WGPU HUD Example");
+    window.set_app_id("io.github.ciantic.wayapp.WgpuHud");
+    window.commit();
+
+    let mut egui_window = EguiSurfaceState::new(&app, &window, 400, 300);
+    let mut myapp = EguiApp {
+        format: egui_window.output_format(),
+        hud_visible: true,
+    };
+
+    app.run_dispatcher();
+
+    'main_loop: loop {
+        if let Ok(event) = rx.recv() {
+            match event {
+                AppEvent::WaylandDispatch(token) => {
+                    let events = app.dispatch_pending(token);
+                    egui_window.handle_events(&mut app, &events, &mut |ui| myapp.ui(ui));
+
+                    for event in events {
+                        if let WaylandEvent::WindowRequestClose(_) = event {
+                            break 'main_loop;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
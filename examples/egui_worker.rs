@@ -0,0 +1,95 @@
+use egui::CentralPanel;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+use wayapp::*;
+
+struct EguiApp {
+    sender: SurfaceSender<u64>,
+    receiver: Receiver<u64>,
+    working: bool,
+    result: Option<u64>,
+}
+
+impl EguiApp {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        // Results from the worker thread
+        for result in self.receiver.try_iter() {
+            self.result = Some(result);
+            self.working = false;
+        }
+
+        CentralPanel::default().show(ui, |ui| {
+            ui.heading("Background worker");
+
+            if self.working {
+                ui.horizontal(|ui| {
+                    ui.add(egui::Spinner::new());
+                    ui.label("Computing...");
+                });
+            } else if ui.button("Compute the 40th Fibonacci number").clicked() {
+                self.working = true;
+                let sender = self.sender.clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(Duration::from_secs(1));
+                    let _ = sender.send(fibonacci(40));
+                });
+            }
+
+            if let Some(result) = self.result {
+                ui.label(format!("Result: {}", result));
+            }
+        });
+    }
+}
+
+fn fibonacci(n: u64) -> u64 {
+    if n < 2 {
+        n
+    } else {
+        fibonacci(n - 1) + fibonacci(n - 2)
+    }
+}
+
+fn main() {
+    unsafe { std::env::set_var("RUST_LOG", "wayapp=trace") };
+    env_logger::init();
+
+    let (tx, rx) = std::sync::mpsc::channel::<DispatchToken>();
+    let mut app = Application::new(move |t| {
+        let _ = tx.send(t);
+    });
+
+    let window = app.xdg_shell.create_window(
+        app.compositor_state.create_surface(&app.qh),
+        WindowDecorations::ServerDefault,
+        &app.qh,
+    );
+    window.set_title("Worker Example");
+    window.set_app_id("io.github.ciantic.wayapp.WorkerExample");
+    window.commit();
+
+    let mut window_app = EguiSurfaceState::new(&app, &window, 300, 200);
+
+    let (sender, receiver) = window_app.channel();
+    let mut myapp = EguiApp {
+        sender,
+        receiver,
+        working: false,
+        result: None,
+    };
+
+    app.run_dispatcher();
+
+    'main_loop: while let Ok(token) = rx.recv() {
+        let events = app.dispatch_pending(token);
+        window_app.handle_events(&mut app, &events, &mut |ui| myapp.ui(ui));
+
+        for event in events {
+            if let WaylandEvent::WindowRequestClose(_) = event {
+                break 'main_loop;
+            }
+        }
+    }
+}
@@ -74,24 +74,19 @@ fn main() {
     let mut child_window_state = SingleColorState::new(&child_window, (255, 0, 255), 128, 128);
 
     // Example subsurface --------------------------
-    // let (subsurface, sub_wlsurface) = app
-    //     .subcompositor_state
-    //     .create_subsurface(example_win_surface.clone(), &app.qh);
-    // subsurface.set_position(20, 20);
-    // trace!(
-    //     "Created subsurface: {:?}",
-    //     sub_wlsurface.id().as_ptr() as usize
-    // );
-    // single_color_manager.push(
-    //     (
-    //         example_win_surface.clone(),
-    //         subsurface.clone(),
-    //         sub_wlsurface.clone(),
-    //     ),
-    //     SingleColorState::new((128, 255, 0)),
-    // );
-
-    // app.push_subsurface(sub_example);
+    // Subsurfaces get no configure, the size is set directly. The buffer is
+    // scaled with the output scale like the other surfaces
+    let (subsurface, sub_wlsurface) = app
+        .subcompositor_state
+        .create_subsurface(example_win_surface.clone(), &app.qh);
+    subsurface.set_position(20, 20);
+    let mut subsurface_state = SingleColorState::new(
+        (example_win_surface.clone(), subsurface, sub_wlsurface),
+        (128, 255, 0),
+        100,
+        30,
+    );
+    subsurface_state.set_size(&app, 100, 30);
 
     // Example popup, attached to example window --------------------------
     let xdg_surface = example_window.xdg_surface();
@@ -122,6 +117,7 @@ fn main() {
                     example_window_state.handle_events(&mut app, &events);
                     child_window_state.handle_events(&mut app, &events);
                     popup_state.handle_events(&mut app, &events);
+                    subsurface_state.handle_events(&mut app, &events);
                 } // Handle other events here
             }
         }
@@ -1,10 +1,14 @@
-// use crate::LayerSurfaceContainer;
-// use crate::PopupContainer;
-// use crate::SubsurfaceContainer;
-// use crate::WindowContainer;
+use crate::BufferedEvent;
+use crate::DeviceOptions;
+use crate::EventBuffer;
+use egui_wgpu::wgpu;
 use log::trace;
+use smithay_client_toolkit::activation::ActivationHandler;
+use smithay_client_toolkit::activation::ActivationState;
+use smithay_client_toolkit::activation::RequestData;
 use smithay_client_toolkit::compositor::CompositorHandler;
 use smithay_client_toolkit::compositor::CompositorState;
+use smithay_client_toolkit::delegate_activation;
 use smithay_client_toolkit::delegate_compositor;
 use smithay_client_toolkit::delegate_keyboard;
 use smithay_client_toolkit::delegate_layer;
@@ -19,6 +23,7 @@ use smithay_client_toolkit::delegate_xdg_popup;
 use smithay_client_toolkit::delegate_xdg_shell;
 use smithay_client_toolkit::delegate_xdg_window;
 use smithay_client_toolkit::output::OutputHandler;
+use smithay_client_toolkit::output::OutputInfo;
 use smithay_client_toolkit::output::OutputState;
 use smithay_client_toolkit::registry::ProvidesRegistryState;
 use smithay_client_toolkit::registry::RegistryState;
@@ -30,11 +35,16 @@ use smithay_client_toolkit::seat::SeatState;
 use smithay_client_toolkit::seat::keyboard::KeyEvent;
 use smithay_client_toolkit::seat::keyboard::KeyboardHandler;
 use smithay_client_toolkit::seat::keyboard::Keysym;
+use smithay_client_toolkit::seat::keyboard::RepeatInfo;
+use smithay_client_toolkit::seat::pointer::CursorIcon;
 use smithay_client_toolkit::seat::pointer::PointerEvent;
 use smithay_client_toolkit::seat::pointer::PointerEventKind;
 use smithay_client_toolkit::seat::pointer::PointerHandler;
+use smithay_client_toolkit::seat::pointer::ThemeSpec;
+use smithay_client_toolkit::seat::pointer::ThemedPointer;
 use smithay_client_toolkit::seat::pointer::cursor_shape::CursorShapeManager;
 use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::wlr_layer::Layer;
 use smithay_client_toolkit::shell::wlr_layer::LayerShell;
 use smithay_client_toolkit::shell::wlr_layer::LayerShellHandler;
 use smithay_client_toolkit::shell::wlr_layer::LayerSurface;
@@ -54,13 +64,17 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
 use wayland_backend::client::ObjectId;
 use wayland_backend::client::WaylandError;
 use wayland_client::Connection;
 use wayland_client::Dispatch;
+use wayland_client::DispatchError;
 use wayland_client::EventQueue;
 use wayland_client::Proxy;
 use wayland_client::QueueHandle;
+use wayland_client::globals::GlobalList;
 use wayland_client::globals::registry_queue_init;
 use wayland_client::protocol::wl_keyboard::WlKeyboard;
 use wayland_client::protocol::wl_output;
@@ -71,12 +85,27 @@ use wayland_client::protocol::wl_seat;
 use wayland_client::protocol::wl_surface::WlSurface;
 use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::Shape;
 use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::WpCursorShapeDeviceV1;
+use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_manager_v1::WpCursorShapeManagerV1;
+use wayland_protocols::wp::fractional_scale::v1::client::wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1;
+use wayland_protocols::wp::fractional_scale::v1::client::wp_fractional_scale_v1;
+use wayland_protocols::wp::fractional_scale::v1::client::wp_fractional_scale_v1::WpFractionalScaleV1;
+use wayland_protocols::wp::idle_inhibit::zv1::client::zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1;
+use wayland_protocols::wp::idle_inhibit::zv1::client::zwp_idle_inhibitor_v1;
+use wayland_protocols::wp::idle_inhibit::zv1::client::zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1;
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_pinch_v1;
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1;
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gestures_v1::ZwpPointerGesturesV1;
 use wayland_protocols::wp::text_input::zv3::client::zwp_text_input_manager_v3::ZwpTextInputManagerV3;
 use wayland_protocols::wp::text_input::zv3::client::zwp_text_input_v3;
 use wayland_protocols::wp::text_input::zv3::client::zwp_text_input_v3::ZwpTextInputV3;
 use wayland_protocols::wp::viewporter::client::wp_viewport::WpViewport;
 use wayland_protocols::wp::viewporter::client::wp_viewport::{self};
 use wayland_protocols::wp::viewporter::client::wp_viewporter::WpViewporter;
+use wayland_protocols::xdg::dialog::v1::client::xdg_dialog_v1;
+use wayland_protocols::xdg::dialog::v1::client::xdg_dialog_v1::XdgDialogV1;
+use wayland_protocols::xdg::dialog::v1::client::xdg_wm_dialog_v1::XdgWmDialogV1;
+use wayland_protocols::xdg::xdg_output::zv1::client::zxdg_output_manager_v1::ZxdgOutputManagerV1;
+use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_shell_v1::ZwlrLayerShellV1;
 
 /// Enum representing different Wayland events
 ///
@@ -84,9 +113,14 @@ use wayland_protocols::wp::viewporter::client::wp_viewporter::WpViewporter;
 /// application-level event enum.
 #[derive(Debug, Clone)]
 pub enum WaylandEvent {
-    /// WlSurface and the timestamp
+    /// Frame callback of a surface with its timestamp in milliseconds
     Frame(WlSurface, u32),
+    /// Frame from the surface's `FrameScheduler`, e.g. a repaint EGUI
+    /// requested, not a frame callback from the compositor
+    ScheduledFrame(WlSurface),
     ScaleFactorChanged(WlSurface, i32),
+    /// Preferred fractional scale of the surface, e.g. `1.5`
+    FractionalScaleChanged(WlSurface, f64),
     TransformChanged(WlSurface),
     SurfaceEnteredOutput(WlSurface, WlOutput),
     SurfaceLeftOutput(WlSurface, WlOutput),
@@ -106,6 +140,15 @@ pub enum WaylandEvent {
     KeyRepeat(KeyEvent),
     PointerEvent((WlSurface, (f64, f64), PointerEventKind)),
     ModifiersChanged(smithay_client_toolkit::seat::keyboard::Modifiers),
+    /// Compositor changed the keyboard repeat rate and delay
+    KeyboardRepeatInfo(RepeatInfo),
+    /// Touchpad pinch gesture started on the surface
+    PinchBegin(WlSurface),
+    /// Touchpad pinch gesture update, scale change (multiplicative) and
+    /// rotation in degrees, both relative to the previous update
+    PinchUpdate(WlSurface, f64, f64),
+    /// Touchpad pinch gesture ended, `true` if it was cancelled
+    PinchEnd(WlSurface, bool),
     ImeEnter(WlSurface),
     ImeLeave(WlSurface),
     /// The text to commit to the input field. `None` clears any pending
@@ -121,13 +164,18 @@ pub enum WaylandEvent {
     ImeDeleteSurroundingText(u32, u32),
     /// Signals that a complete set of IME events has been sent for this serial.
     ImeDone(u32),
+    /// The Wayland connection failed, e.g. due to a protocol error. No more
+    /// events will arrive, the app should shut down.
+    ConnectionError(String),
 }
 
 impl WaylandEvent {
     pub fn get_wl_surface(&self) -> Option<&WlSurface> {
         match self {
             WaylandEvent::Frame(s, _) => Some(s),
+            WaylandEvent::ScheduledFrame(s) => Some(s),
             WaylandEvent::ScaleFactorChanged(s, _) => Some(s),
+            WaylandEvent::FractionalScaleChanged(s, _) => Some(s),
             WaylandEvent::TransformChanged(s) => Some(s),
             WaylandEvent::SurfaceEnteredOutput(s, _) => Some(s),
             WaylandEvent::SurfaceLeftOutput(s, _) => Some(s),
@@ -139,30 +187,70 @@ impl WaylandEvent {
             WaylandEvent::KeyboardEnter(s, _, _) => Some(s),
             WaylandEvent::KeyboardLeave(s) => Some(s),
             WaylandEvent::PointerEvent((s, _, _)) => Some(s),
+            WaylandEvent::PinchBegin(s) => Some(s),
+            WaylandEvent::PinchUpdate(s, _, _) => Some(s),
+            WaylandEvent::PinchEnd(s, _) => Some(s),
             WaylandEvent::ImeEnter(s) => Some(s),
             WaylandEvent::ImeLeave(s) => Some(s),
             _ => None,
         }
     }
+
+    /// Whether this is a configure of a window, layer surface or popup
+    pub fn is_configure(&self) -> bool {
+        matches!(
+            self,
+            WaylandEvent::WindowConfigure(..)
+                | WaylandEvent::LayerShellConfigure(..)
+                | WaylandEvent::PopupConfigure(..)
+        )
+    }
 }
 
 /// External dispatcher for emitting Wayland events from outside the application
 #[derive(Clone)]
 pub struct WaylandEventEmitter {
     dispatch_fn: Arc<dyn Fn(DispatchToken) + Send + Sync + 'static>,
-    events: Arc<Mutex<Vec<WaylandEvent>>>,
+    events: Arc<Mutex<EventBuffer<WaylandEvent>>>,
 }
 
 impl WaylandEventEmitter {
     /// Emit events to the application
     pub fn emit_events(&self, events: Vec<WaylandEvent>) {
-        self.events.lock().unwrap().extend(events);
+        let mut buffer = self.events.lock().unwrap();
+        for event in events {
+            buffer.push(event);
+        }
+        drop(buffer);
         (self.dispatch_fn)(DispatchToken::external());
     }
 }
 
+/// FPS cap of EGUI surfaces in `Application::set_low_power` mode
+pub const LOW_POWER_FPS: f32 = 30.0;
+
+/// Default of `Application::set_max_buffered_events`
+pub const DEFAULT_MAX_BUFFERED_EVENTS: usize = 4096;
+
+impl BufferedEvent for WaylandEvent {
+    fn is_motion(&self) -> bool {
+        matches!(
+            self,
+            WaylandEvent::PointerEvent((_, _, PointerEventKind::Motion { .. }))
+        )
+    }
+
+    /// Motions are coalesced per surface
+    fn replaces(&self, previous: &Self) -> bool {
+        self.get_wl_surface() == previous.get_wl_surface()
+    }
+}
+
+/// Handler of `Application::set_protocol_error_handler`
+type ProtocolErrorHandler = Box<dyn Fn(&str)>;
+
 pub struct Application {
-    wayland_events: Arc<Mutex<Vec<WaylandEvent>>>,
+    wayland_events: Arc<Mutex<EventBuffer<WaylandEvent>>>,
     pub conn: Connection,
     event_queue: Option<EventQueue<Self>>,
     pub qh: QueueHandle<Self>,
@@ -178,14 +266,45 @@ pub struct Application {
     pub viewporter: SimpleGlobal<WpViewporter, 1>,
     pub text_input_manager: Option<SimpleGlobal<ZwpTextInputManagerV3, 1>>,
     pub text_input: Option<ZwpTextInputV3>,
-
-    cursor_shape_manager: CursorShapeManager,
+    pub pointer_gestures: Option<SimpleGlobal<ZwpPointerGesturesV1, 1>>,
+    pub fractional_scale_manager: Option<SimpleGlobal<WpFractionalScaleManagerV1, 1>>,
+    pub dialog_manager: Option<SimpleGlobal<XdgWmDialogV1, 1>>,
+    pub idle_inhibit_manager: Option<SimpleGlobal<ZwpIdleInhibitManagerV1, 1>>,
+    pub activation: Option<ActivationState>,
+
+    cursor_shape_manager: Option<CursorShapeManager>,
+    cursor: Shape,
+    cursor_theme: ThemeSpec<'static>,
+    themed_pointer: Option<ThemedPointer>,
     last_pointer_enter_serial: Option<u32>,
     last_pointer: Option<WlPointer>,
+    last_pointer_button_serial: Option<u32>,
+    pointer_seat: Option<wl_seat::WlSeat>,
     pointer_shape_devices: HashMap<ObjectId, WpCursorShapeDeviceV1>,
     keyboard_focused_surface: Option<ObjectId>,
+    keyboard_repeat_info: Option<RepeatInfo>,
+    pinch_surface: Option<WlSurface>,
+    pinch_scale: f64,
+    egui_visuals: Option<egui::Visuals>,
+    egui_visuals_generation: u64,
+    egui_fonts: Vec<egui::epaint::text::FontInsert>,
+    shared_textures: Vec<Option<SharedTextureImage>>,
+    shared_textures_generation: u64,
+    prefer_10bit_color: bool,
+    gpu_power_preference: wgpu::PowerPreference,
+    gpu_memory_hints: wgpu::MemoryHints,
+    low_power: bool,
+    ui_scale: f32,
+    redraw_generation: u64,
+    reconfigure_generation: u64,
+    dispatch_metrics: DispatchMetrics,
+    dispatch_started: Instant,
+    pointer_state: PointerState,
     dispatcher: Option<InternalDispatcherThread>,
     dispatch_fn: Arc<dyn Fn(DispatchToken) + Send + Sync + 'static>,
+    globals: GlobalList,
+    protocol_error_handler: Option<ProtocolErrorHandler>,
+    connection_error_reported: bool,
 }
 
 impl Application {
@@ -205,11 +324,28 @@ impl Application {
         let xdg_shell = XdgShell::bind(&globals, &qh).expect("xdg shell not available");
         let shm_state = Shm::bind(&globals, &qh).expect("wl_shm not available");
         let layer_shell = LayerShell::bind(&globals, &qh).expect("layer shell not available");
-        let cursor_shape_manager =
-            CursorShapeManager::bind(&globals, &qh).expect("cursor shape manager not available");
+        // Optional, cursors fall back to the XCursor theme without it
+        let cursor_shape_manager = CursorShapeManager::bind(&globals, &qh).ok();
         let viewporter = SimpleGlobal::<WpViewporter, 1>::bind(&globals, &qh)
             .expect("wp_viewporter not available");
 
+        // Optional, touchpad gestures are just not emitted without it
+        let pointer_gestures = SimpleGlobal::<ZwpPointerGesturesV1, 1>::bind(&globals, &qh).ok();
+
+        // Optional, integer buffer scale is used without it
+        let fractional_scale_manager =
+            SimpleGlobal::<WpFractionalScaleManagerV1, 1>::bind(&globals, &qh).ok();
+
+        // Optional, dialogs can't be made modal without it
+        let dialog_manager = SimpleGlobal::<XdgWmDialogV1, 1>::bind(&globals, &qh).ok();
+
+        // Optional, the screen can't be kept awake without it
+        let idle_inhibit_manager =
+            SimpleGlobal::<ZwpIdleInhibitManagerV1, 1>::bind(&globals, &qh).ok();
+
+        // Optional, surfaces can't request attention without it
+        let activation = ActivationState::bind(&globals, &qh).ok();
+
         // TODO: Enable IME support in future
         // let text_input_manager = SimpleGlobal::<ZwpTextInputManagerV3,
         // 1>::bind(&globals, &qh) .expect("zwp_text_input_manager_v3 not
@@ -218,7 +354,7 @@ impl Application {
         let clipboard = unsafe { Clipboard::new(conn.display().id().as_ptr() as *mut _) };
 
         Self {
-            wayland_events: Arc::new(Mutex::new(Vec::new())),
+            wayland_events: Arc::new(Mutex::new(EventBuffer::new(DEFAULT_MAX_BUFFERED_EVENTS))),
             event_queue: Some(event_queue),
             conn,
             qh: qh.clone(),
@@ -234,19 +370,87 @@ impl Application {
             viewporter,
             text_input_manager: None,
             text_input: None,
+            pointer_gestures,
+            fractional_scale_manager,
+            dialog_manager,
+            idle_inhibit_manager,
+            activation,
             cursor_shape_manager,
+            cursor: Shape::Default,
+            cursor_theme: ThemeSpec::System,
+            themed_pointer: None,
             last_pointer_enter_serial: None,
             last_pointer: None,
+            last_pointer_button_serial: None,
+            pointer_seat: None,
             pointer_shape_devices: HashMap::new(),
             keyboard_focused_surface: None,
+            keyboard_repeat_info: None,
+            pinch_surface: None,
+            pinch_scale: 1.0,
+            egui_visuals: None,
+            egui_visuals_generation: 0,
+            egui_fonts: Vec::new(),
+            shared_textures: Vec::new(),
+            shared_textures_generation: 0,
+            prefer_10bit_color: false,
+            gpu_power_preference: wgpu::PowerPreference::default(),
+            gpu_memory_hints: wgpu::MemoryHints::MemoryUsage,
+            low_power: false,
+            ui_scale: ui_scale_from_env(),
+            redraw_generation: 0,
+            reconfigure_generation: 0,
+            dispatch_metrics: DispatchMetrics::default(),
+            dispatch_started: Instant::now(),
+            pointer_state: PointerState::default(),
             dispatcher: None,
             dispatch_fn: Arc::new(dispatch_fn),
+            globals,
+            protocol_error_handler: None,
+            connection_error_reported: false,
         }
     }
 
+    /// Like `take_wayland_events`, grouped by surface, see
+    /// `GroupedWaylandEvents`
+    pub fn take_wayland_events_grouped(&mut self) -> GroupedWaylandEvents {
+        self.take_wayland_events().into()
+    }
+
     pub fn take_wayland_events(&mut self) -> Vec<WaylandEvent> {
-        let mut events = self.wayland_events.lock().unwrap();
-        events.drain(..).collect()
+        let events = self.wayland_events.lock().unwrap().take();
+        self.dispatch_started = Instant::now();
+        self.dispatch_metrics = DispatchMetrics {
+            events: events.len(),
+            ..Default::default()
+        };
+        events
+    }
+
+    /// Limit the events buffered between dispatches, defaults to
+    /// `DEFAULT_MAX_BUFFERED_EVENTS`
+    ///
+    /// Keeps a slow frame from piling up thousands of pointer motions.
+    /// Consecutive motions are coalesced into the latest, and past the limit
+    /// the oldest motion is dropped. Other events are never dropped. The same
+    /// limit applies to the EGUI input of each surface.
+    pub fn set_max_buffered_events(&mut self, max: usize) {
+        self.wayland_events.lock().unwrap().set_max(max);
+    }
+
+    pub(crate) fn max_buffered_events(&self) -> usize {
+        self.wayland_events.lock().unwrap().max()
+    }
+
+    /// Metrics of the latest dispatch, see `DispatchMetrics`
+    pub fn last_dispatch_metrics(&self) -> DispatchMetrics {
+        self.dispatch_metrics
+    }
+
+    /// Called by surfaces when they have handled the events of a dispatch
+    pub(crate) fn record_dispatch_handled(&mut self, renders: usize) {
+        self.dispatch_metrics.renders += renders;
+        self.dispatch_metrics.duration = self.dispatch_started.elapsed();
     }
 
     pub fn get_event_emitter(&self) -> WaylandEventEmitter {
@@ -260,9 +464,22 @@ impl Application {
         self.wayland_events.lock().unwrap().push(event);
     }
 
+    /// Set the cursor of the pointer over the app's surfaces
+    ///
+    /// Uses `wp_cursor_shape_v1`, or the XCursor theme when the compositor
+    /// doesn't support it, see `set_cursor_theme`.
     pub fn set_cursor(&mut self, shape: Shape) {
+        self.cursor = shape;
+        if let Some(themed_pointer) = &self.themed_pointer {
+            let icon = shape_to_cursor_icon(shape);
+            if let Err(err) = themed_pointer.set_cursor(&self.conn, icon) {
+                trace!("[COMMON] Failed to set themed cursor {:?}: {:?}", icon, err);
+            }
+            return;
+        }
         if let Some(serial) = self.last_pointer_enter_serial
             && let Some(pointer) = &self.last_pointer
+            && let Some(cursor_shape_manager) = &self.cursor_shape_manager
         {
             let pointer_id = pointer.id();
             let device = self
@@ -273,13 +490,408 @@ impl Application {
                         "[COMMON] Creating new cursor shape device for pointer id {}",
                         pointer.id()
                     );
-                    self.cursor_shape_manager
-                        .get_shape_device(pointer, &self.qh)
+                    cursor_shape_manager.get_shape_device(pointer, &self.qh)
                 });
             device.set_shape(serial, shape);
         }
     }
 
+    /// Cursor shape last set with `set_cursor`
+    pub fn cursor(&self) -> Shape {
+        self.cursor
+    }
+
+    /// XCursor theme used when the compositor doesn't support cursor shapes,
+    /// defaults to the `XCURSOR_THEME` and `XCURSOR_SIZE` of the environment
+    ///
+    /// The theme is loaded when the seat's pointer is created, so this must
+    /// be called before `run_dispatcher`.
+    pub fn set_cursor_theme(&mut self, theme: ThemeSpec<'static>) {
+        self.cursor_theme = theme;
+    }
+
+    /// Check if the compositor advertises a global, e.g.
+    /// `app.has_global::<WpViewporter>()`
+    pub fn has_global<I: Proxy>(&self) -> bool {
+        self.has_global_interface(I::interface().name)
+    }
+
+    /// Check if the compositor advertises a global by interface name, e.g.
+    /// `"wp_viewporter"`
+    pub fn has_global_interface(&self, interface: &str) -> bool {
+        self.globals
+            .contents()
+            .with_list(|list| list.iter().any(|global| global.interface == interface))
+    }
+
+    /// Advertised globals and a best-effort guess of the compositor, e.g.
+    /// for bug reports
+    pub fn compositor_info(&self) -> CompositorInfo {
+        let globals: Vec<(String, u32)> = self.globals.contents().with_list(|list| {
+            list.iter()
+                .map(|global| (global.interface.clone(), global.version))
+                .collect()
+        });
+        let version = |interface: &str| {
+            globals
+                .iter()
+                .find(|(name, _)| name == interface)
+                .map(|(_, version)| *version)
+        };
+        CompositorInfo {
+            name: guess_compositor(&globals),
+            wl_compositor_version: version("wl_compositor"),
+            xdg_wm_base_version: version("xdg_wm_base"),
+            globals,
+        }
+    }
+
+    pub fn has_layer_shell(&self) -> bool {
+        self.has_global::<ZwlrLayerShellV1>()
+    }
+
+    pub fn has_viewporter(&self) -> bool {
+        self.has_global::<WpViewporter>()
+    }
+
+    pub fn has_cursor_shape(&self) -> bool {
+        self.has_global::<WpCursorShapeManagerV1>()
+    }
+
+    pub fn has_fractional_scale(&self) -> bool {
+        self.has_global::<WpFractionalScaleManagerV1>()
+    }
+
+    /// Serial of the last pointer button press
+    ///
+    /// Needed for requests that must be tied to user input, such as
+    /// interactive move and resize of windows.
+    pub fn last_pointer_serial(&self) -> Option<u32> {
+        self.last_pointer_button_serial
+    }
+
+    /// Move the pointer focus to a surface
+    pub(crate) fn pointer_entered(&mut self, surface: WlSurface) {
+        self.pointer_state.surface = Some(surface);
+        // The cursor of the previous surface would stay until this surface
+        // sets its own
+        self.set_cursor(Shape::Default);
+    }
+
+    /// Current pointer position, pressed buttons and keyboard modifiers
+    ///
+    /// Updated as Wayland events are dispatched, e.g. for custom drags
+    /// outside EGUI widgets.
+    pub fn pointer_state(&self) -> &PointerState {
+        &self.pointer_state
+    }
+
+    /// Seat of the pointer
+    pub fn pointer_seat(&self) -> Option<&wl_seat::WlSeat> {
+        self.pointer_seat.as_ref()
+    }
+
+    /// Keyboard repeat configuration advertised by the compositor
+    ///
+    /// Returns `(rate, delay)`, rate in characters per second and delay in
+    /// milliseconds. `None` if the compositor hasn't sent it yet or key
+    /// repeat is disabled.
+    pub fn keyboard_repeat_info(&self) -> Option<(u32, u32)> {
+        match self.keyboard_repeat_info {
+            Some(RepeatInfo::Repeat { rate, delay }) => Some((rate.get(), delay)),
+            _ => None,
+        }
+    }
+
+    /// Set EGUI visuals (theme) for all EGUI surfaces
+    ///
+    /// Surfaces pick up the change on the next dispatch and redraw, so this
+    /// can be used to switch between light and dark at runtime. Calling
+    /// `set_visuals` in `ui()` still overrides this for that surface.
+    pub fn set_egui_visuals(&mut self, visuals: egui::Visuals) {
+        self.egui_visuals = Some(visuals);
+        self.egui_visuals_generation += 1;
+
+        // Wake up the event loop so surfaces apply the visuals
+        self.get_event_emitter().emit_events(vec![]);
+    }
+
+    /// EGUI visuals set with `set_egui_visuals`
+    pub fn egui_visuals(&self) -> Option<&egui::Visuals> {
+        self.egui_visuals.as_ref()
+    }
+
+    pub(crate) fn egui_visuals_generation(&self) -> u64 {
+        self.egui_visuals_generation
+    }
+
+    /// Prefer a 10-bit surface format (`Rgb10a2Unorm`) for EGUI surfaces
+    ///
+    /// Experimental: for wide gamut and HDR capable outputs, with less
+    /// banding in gradients. Applies to surfaces created afterwards, and only
+    /// when the GPU and compositor support the format, otherwise the default
+    /// 8-bit format is used. Color spaces aren't negotiated, the compositor
+    /// still assumes sRGB content.
+    pub fn set_prefer_10bit_color(&mut self, prefer: bool) {
+        self.prefer_10bit_color = prefer;
+    }
+
+    /// Set the GPU power preference of EGUI surfaces, e.g. `LowPower` for
+    /// the integrated GPU on battery
+    ///
+    /// Applies to surfaces created afterwards, a device can't change it after
+    /// creation. See `set_low_power` for runtime frame pacing.
+    pub fn set_gpu_power_preference(&mut self, preference: wgpu::PowerPreference) {
+        self.gpu_power_preference = preference;
+    }
+
+    /// Set the memory allocation hints of EGUI surface devices, defaults to
+    /// `MemoryHints::MemoryUsage`
+    ///
+    /// Applies to surfaces created afterwards.
+    pub fn set_gpu_memory_hints(&mut self, hints: wgpu::MemoryHints) {
+        self.gpu_memory_hints = hints;
+    }
+
+    pub(crate) fn device_options(&self) -> DeviceOptions {
+        DeviceOptions {
+            prefer_10bit: self.prefer_10bit_color,
+            power_preference: self.gpu_power_preference,
+            memory_hints: self.gpu_memory_hints.clone(),
+            renderer: Default::default(),
+        }
+    }
+
+    /// Reduce GPU usage, e.g. on battery
+    ///
+    /// Caps the FPS target of every EGUI surface to `LOW_POWER_FPS`. The
+    /// power preference of existing devices can't change, so at runtime this
+    /// only adjusts frame pacing.
+    pub fn set_low_power(&mut self, low_power: bool) {
+        self.low_power = low_power;
+        self.get_event_emitter().emit_events(vec![]);
+    }
+
+    pub fn is_low_power(&self) -> bool {
+        self.low_power
+    }
+
+    /// Ask the compositor to draw attention to the surface, e.g. by flashing
+    /// its taskbar entry
+    ///
+    /// Uses xdg-activation without a user interaction serial, which most
+    /// compositors show as an urgency hint instead of focusing the surface.
+    /// Support varies, returns `false` when xdg-activation isn't available.
+    pub fn request_attention(&self, surface: &WlSurface) -> bool {
+        let Some(activation) = &self.activation else {
+            return false;
+        };
+        // The surface is activated when the compositor sends the token, see
+        // `ActivationHandler::new_token`
+        activation.request_token(
+            &self.qh,
+            RequestData {
+                app_id: None,
+                seat_and_serial: None,
+                surface: Some(surface.clone()),
+            },
+        );
+        true
+    }
+
+    /// Initial zoom of EGUI surfaces, read from `WAYAPP_UI_SCALE` (e.g. `1.5`)
+    ///
+    /// Multiplies the compositor scale, so users can enlarge every UI without
+    /// code changes. Defaults to `1.0`, `EguiSurfaceState::set_zoom` overrides
+    /// it for a surface.
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+
+    /// Add a font to all EGUI surfaces, existing and future ones
+    ///
+    /// The font is a fallback for glyphs the default fonts lack, e.g. CJK, see
+    /// `EguiSurfaceState::add_font`.
+    pub fn add_egui_font(
+        &mut self,
+        name: impl Into<String>,
+        bytes: Vec<u8>,
+        family: egui::FontFamily,
+    ) {
+        self.egui_fonts
+            .push(font_insert(name.into(), bytes, family));
+
+        // Wake up the event loop so surfaces add the font
+        self.get_event_emitter().emit_events(vec![]);
+    }
+
+    /// Fonts added with `add_egui_font`, in order
+    pub(crate) fn egui_fonts(&self) -> &[egui::epaint::text::FontInsert] {
+        &self.egui_fonts
+    }
+
+    /// Register a texture on all EGUI surfaces, existing and future ones,
+    /// e.g. an icon atlas
+    ///
+    /// The image is kept once in the application, but each surface has its
+    /// own WGPU device, so it's uploaded to each surface's GPU memory. Get
+    /// the id for `ui()` with `SharedTexture::id`. The texture lives until
+    /// `remove_texture`, or as long as the surface.
+    pub fn register_texture(
+        &mut self,
+        name: impl Into<String>,
+        image: egui::ColorImage,
+        options: egui::TextureOptions,
+    ) -> SharedTexture {
+        self.shared_textures.push(Some(SharedTextureImage {
+            name: name.into(),
+            image: Arc::new(image),
+            options,
+        }));
+        self.shared_textures_generation += 1;
+
+        // Wake up the event loop so surfaces upload the texture
+        self.get_event_emitter().emit_events(vec![]);
+        SharedTexture(self.shared_textures.len() - 1)
+    }
+
+    /// Free a texture of `register_texture` on all surfaces
+    pub fn remove_texture(&mut self, texture: SharedTexture) {
+        if let Some(slot) = self.shared_textures.get_mut(texture.0)
+            && slot.take().is_some()
+        {
+            self.shared_textures_generation += 1;
+            self.get_event_emitter().emit_events(vec![]);
+        }
+    }
+
+    pub(crate) fn shared_textures(&self) -> &[Option<SharedTextureImage>] {
+        &self.shared_textures
+    }
+
+    pub(crate) fn shared_textures_generation(&self) -> u64 {
+        self.shared_textures_generation
+    }
+
+    /// Repaint every surface on its next `handle_events`
+    ///
+    /// Useful after changing state the UI closures read, e.g. a theme or
+    /// locale. Applies to windows, layer surfaces and popups alike.
+    pub fn redraw_all(&mut self) {
+        self.redraw_generation += 1;
+        self.get_event_emitter().emit_events(vec![]);
+    }
+
+    /// Reconfigure the WGPU surface of every surface and repaint it
+    ///
+    /// Use when the GPU side needs to be rebuilt, e.g. after a display
+    /// change left the swapchain in a bad state.
+    pub fn reconfigure_all(&mut self) {
+        self.reconfigure_generation += 1;
+        self.get_event_emitter().emit_events(vec![]);
+    }
+
+    pub(crate) fn redraw_generation(&self) -> u64 {
+        self.redraw_generation
+    }
+
+    pub(crate) fn reconfigure_generation(&self) -> u64 {
+        self.reconfigure_generation
+    }
+
+    /// Create a layer surface on a specific output
+    ///
+    /// Use with `PerOutputSurfaces` to keep one surface per monitor.
+    pub fn create_layer_surface_on(
+        &self,
+        output: &WlOutput,
+        layer: Layer,
+        namespace: Option<impl Into<String>>,
+    ) -> LayerSurface {
+        self.layer_shell.create_layer_surface(
+            &self.qh,
+            self.compositor_state.create_surface(&self.qh),
+            layer,
+            namespace,
+            Some(output),
+        )
+    }
+
+    /// Outputs with their info, e.g. for placing a layer surface per monitor
+    ///
+    /// With `zxdg_output_manager_v1` the `logical_position` and `logical_size`
+    /// of the info are exact in the compositor's logical space, also with
+    /// mixed scales. Without it they're `None`, see `has_xdg_output`.
+    pub fn outputs(&self) -> Vec<(WlOutput, OutputInfo)> {
+        self.output_state
+            .outputs()
+            .filter_map(|output| {
+                let info = self.output_state.info(&output)?;
+                Some((output, info))
+            })
+            .collect()
+    }
+
+    /// Logical `(x, y, width, height)` of an output, see `outputs`
+    pub fn output_logical_geometry(&self, output: &WlOutput) -> Option<(i32, i32, i32, i32)> {
+        let info = self.output_state.info(output)?;
+        let (x, y) = info.logical_position?;
+        let (width, height) = info.logical_size?;
+        Some((x, y, width, height))
+    }
+
+    /// Whether the compositor supports `zxdg_output_manager_v1`, which gives
+    /// the logical position and size of `outputs`
+    ///
+    /// Without it, `output_logical_geometry` returns `None` and only the
+    /// modes and integer scale of the outputs are known.
+    pub fn has_xdg_output(&self) -> bool {
+        self.has_global::<ZxdgOutputManagerV1>()
+    }
+
+    /// Globals advertised by the compositor, for binding protocols wayapp
+    /// doesn't wrap
+    ///
+    /// Bind with `globals().bind(&app.qh, ..)`, events of the new objects are
+    /// dispatched to `Application`, so implement `Dispatch` for it with your
+    /// own user data type, which the orphan rules allow outside this crate.
+    /// See the egui_idle_inhibit.rs example.
+    pub fn globals(&self) -> &GlobalList {
+        &self.globals
+    }
+
+    /// Set a handler for fatal Wayland connection errors
+    ///
+    /// Protocol errors (e.g. zero sized viewport destination) kill the
+    /// connection. The handler receives a description with the interface,
+    /// object id, error code and message. Afterwards
+    /// `WaylandEvent::ConnectionError` is emitted so the app can shut down.
+    /// Without a handler the error is logged.
+    pub fn set_protocol_error_handler(&mut self, handler: impl Fn(&str) + 'static) {
+        self.protocol_error_handler = Some(Box::new(handler));
+    }
+
+    /// Report a connection error once, via the handler and as an event
+    fn report_connection_error(&mut self, error: String) {
+        if self.connection_error_reported {
+            return;
+        }
+        self.connection_error_reported = true;
+        match &self.protocol_error_handler {
+            Some(handler) => handler(&error),
+            None => log::error!("[COMMON] {}", error),
+        }
+        self.push_wayland_event(WaylandEvent::ConnectionError(error));
+    }
+
+    /// Flush requests to the compositor, a failure is reported like the
+    /// other connection errors
+    pub(crate) fn flush(&mut self) {
+        if let Err(err) = self.conn.flush() {
+            self.report_connection_error(describe_wayland_error(&err));
+        }
+    }
+
     /// Asynchronous way to run the Wayland event loop
     ///
     /// Connection reading happens blockingly in separate thread, but
@@ -297,6 +909,33 @@ impl Application {
         self.dispatcher = Some(dispatcher);
     }
 
+    /// Block until the compositor has processed all requests sent so far
+    ///
+    /// For synchronous setup, e.g. after creating and committing a window,
+    /// the roundtrip receives its first `WindowConfigure` with the real size.
+    /// Events received meanwhile are returned by the next `dispatch_pending`.
+    ///
+    /// Fails with an I/O error if called after `run_dispatcher` or
+    /// `insert_into_loop`, the event queue is read elsewhere then.
+    pub fn roundtrip(&mut self) -> Result<usize, DispatchError> {
+        let Some(mut event_queue) = self.event_queue.take() else {
+            return Err(DispatchError::Backend(WaylandError::Io(
+                std::io::Error::other("Event queue already used by the dispatcher"),
+            )));
+        };
+        let result = event_queue.roundtrip(self);
+        self.event_queue = Some(event_queue);
+        // Wakes the loop to dispatch the received events
+        self.get_event_emitter().emit_events(vec![]);
+        result
+    }
+
+    /// Take the event queue for an external event loop, see `insert_into_loop`
+    #[cfg(feature = "calloop")]
+    pub(crate) fn take_event_queue(&mut self) -> EventQueue<Self> {
+        self.event_queue.take().expect("Event queue already used")
+    }
+
     /// Dispatch pending events, and return collected Wayland events
     ///
     /// Used with `run_dispatcher` method, when dispatch_fn is signaled
@@ -306,7 +945,7 @@ impl Application {
         //    trigger dispatching)
         // 2. Events coming from Wayland connection
         if !token.from_wayland() {
-            self.wayland_events.lock().unwrap().drain(..).collect()
+            self.take_wayland_events()
         } else {
             if let Some(mut dispatcher) = self.dispatcher.take() {
                 let res = dispatcher.dispatch_pending(self);
@@ -319,6 +958,177 @@ impl Application {
     }
 }
 
+/// Pointer state tracked by `Application`, see `Application::pointer_state`
+#[derive(Debug, Clone, Default)]
+pub struct PointerState {
+    /// Surface under the pointer, `None` when over no surface of this app
+    pub surface: Option<WlSurface>,
+    /// Surface-local position in logical pixels
+    pub position: (f64, f64),
+    /// Linux evdev codes of the held buttons, e.g. `0x110` for left
+    pub buttons: Vec<u32>,
+    pub modifiers: smithay_client_toolkit::seat::keyboard::Modifiers,
+}
+
+/// Texture registered on all EGUI surfaces, see
+/// `Application::register_texture`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SharedTexture(pub(crate) usize);
+
+impl SharedTexture {
+    /// Id of the texture in the surface of `ctx`, e.g. `ui.ctx()`
+    ///
+    /// `None` until the surface has uploaded it in `handle_events`, or after
+    /// `Application::remove_texture`.
+    pub fn id(self, ctx: &egui::Context) -> Option<egui::TextureId> {
+        ctx.data(|data| data.get_temp::<egui::TextureHandle>(self.egui_id()))
+            .map(|handle| handle.id())
+    }
+
+    /// Key of the surface's texture handle in the EGUI context data
+    pub(crate) fn egui_id(self) -> egui::Id {
+        egui::Id::new(("wayapp_shared_texture", self.0))
+    }
+}
+
+pub(crate) struct SharedTextureImage {
+    pub name: String,
+    pub image: Arc<egui::ColorImage>,
+    pub options: egui::TextureOptions,
+}
+
+/// Wayland events grouped by surface, e.g. `dispatch_pending(token).into()`
+///
+/// For apps with many surfaces, each surface gets only its own events
+/// instead of filtering the whole dispatch. Events of no particular surface
+/// (modifiers, key presses, outputs) are shared by all surfaces and merged in
+/// order with the own events of each, as e.g. key presses go to the surface
+/// with keyboard focus.
+#[derive(Debug, Default)]
+pub struct GroupedWaylandEvents {
+    /// Events of each surface with their position in the dispatch
+    by_surface: HashMap<ObjectId, Vec<(usize, WaylandEvent)>>,
+    /// Events of no particular surface with their position in the dispatch
+    global: Vec<(usize, WaylandEvent)>,
+}
+
+impl GroupedWaylandEvents {
+    /// Events for `surface` in dispatch order, e.g. for
+    /// `EguiSurfaceState::handle_grouped_events`
+    ///
+    /// Only the global events if the surface had none of its own.
+    pub fn for_surface(&self, surface: &WlSurface) -> SurfaceEvents<'_> {
+        SurfaceEvents {
+            own: self
+                .by_surface
+                .get(&surface.id())
+                .map_or(&[], Vec::as_slice),
+            global: &self.global,
+        }
+    }
+
+    /// Events of no particular surface
+    pub fn global(&self) -> impl Iterator<Item = &WaylandEvent> {
+        self.global.iter().map(|(_, event)| event)
+    }
+
+    /// Surfaces that got events of their own
+    pub fn surfaces(&self) -> impl Iterator<Item = &ObjectId> {
+        self.by_surface.keys()
+    }
+}
+
+impl From<Vec<WaylandEvent>> for GroupedWaylandEvents {
+    fn from(events: Vec<WaylandEvent>) -> Self {
+        let mut grouped = GroupedWaylandEvents::default();
+        for (index, event) in events.into_iter().enumerate() {
+            match event.get_wl_surface().map(Proxy::id) {
+                Some(id) => grouped
+                    .by_surface
+                    .entry(id)
+                    .or_default()
+                    .push((index, event)),
+                None => grouped.global.push((index, event)),
+            }
+        }
+        grouped
+    }
+}
+
+/// Events of one surface merged with the global events, see
+/// `GroupedWaylandEvents::for_surface`
+#[derive(Debug, Clone)]
+pub struct SurfaceEvents<'a> {
+    own: &'a [(usize, WaylandEvent)],
+    global: &'a [(usize, WaylandEvent)],
+}
+
+impl<'a> Iterator for SurfaceEvents<'a> {
+    type Item = &'a WaylandEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let own_first = match (self.own.first(), self.global.first()) {
+            (Some((own, _)), Some((global, _))) => own < global,
+            (own, _) => own.is_some(),
+        };
+        let events = if own_first {
+            &mut self.own
+        } else {
+            &mut self.global
+        };
+        let ((_, event), rest) = events.split_first()?;
+        *events = rest;
+        Some(event)
+    }
+}
+
+/// Compositor details, see `Application::compositor_info`
+#[derive(Debug, Clone, Default)]
+pub struct CompositorInfo {
+    /// Guessed from compositor specific globals, e.g. `"KWin"`, `None` if
+    /// nothing distinctive is advertised
+    pub name: Option<&'static str>,
+    pub wl_compositor_version: Option<u32>,
+    pub xdg_wm_base_version: Option<u32>,
+    /// All advertised global interfaces and their versions
+    pub globals: Vec<(String, u32)>,
+}
+
+/// Identify the compositor by the prefixes of its private protocols
+fn guess_compositor(globals: &[(String, u32)]) -> Option<&'static str> {
+    // Checked in order, e.g. Hyprland also advertises wlroots protocols
+    const PREFIXES: &[(&str, &str)] = &[
+        ("hyprland_", "Hyprland"),
+        // wlroots also has `org_kde_kwin_server_decoration_manager`
+        ("org_kde_plasma_", "KWin"),
+        ("gtk_shell", "Mutter"),
+        ("zcosmic_", "COSMIC"),
+        ("cosmic_", "COSMIC"),
+        ("treeland_", "Treeland"),
+        ("weston_", "Weston"),
+        ("river_", "River"),
+        ("zriver_", "River"),
+        ("zwlr_", "wlroots based"),
+    ];
+    PREFIXES.iter().find_map(|(prefix, name)| {
+        globals
+            .iter()
+            .any(|(interface, _)| interface.starts_with(prefix))
+            .then_some(*name)
+    })
+}
+
+/// What happened during one dispatch, e.g. to diagnose render storms
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct DispatchMetrics {
+    /// Wayland events returned by the dispatch
+    pub events: usize,
+    /// GPU renders done by all surfaces while handling the events
+    pub renders: usize,
+    /// Time from taking the events until the last surface handled them
+    pub duration: Duration,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
 pub struct DispatchToken(bool);
 
@@ -403,7 +1213,9 @@ impl InternalDispatcherThread {
                         break;
                     }
                     Err(InternalDispatcherError::WaylandError(e)) => {
-                        eprintln!("Error in Wayland reader thread: {:?}", e);
+                        log::error!("[COMMON] Error in Wayland reader thread: {:?}", e);
+                        // Dispatch once more so the error gets reported
+                        (dispatch_fn)(DispatchToken::wayland());
                         break;
                     }
                 }
@@ -437,8 +1249,7 @@ impl InternalDispatcherThread {
             guard.read_without_dispatch()?;
         } else {
             // Goal is that this branch is never or very seldomly hit
-            #[cfg(feature = "_example")]
-            println!("♦️♦️♦️♦️♦️ Failed to read");
+            trace!("[MAIN] Events were queued before reading, skipping read");
         }
 
         (dispatch_fn)(DispatchToken::wayland());
@@ -447,15 +1258,35 @@ impl InternalDispatcherThread {
 
     /// Dispatch pending events, and return collected Wayland events
     pub(crate) fn dispatch_pending(&mut self, app: &mut Application) -> Vec<WaylandEvent> {
-        let count = self
-            .event_queue
-            .dispatch_pending(app)
-            .expect("Wayland dispatch failed");
+        let count = match self.event_queue.dispatch_pending(app) {
+            Ok(count) => count,
+            Err(DispatchError::Backend(e)) => {
+                app.report_connection_error(describe_wayland_error(&e));
+                0
+            }
+            Err(e) => {
+                app.report_connection_error(format!("Wayland dispatch failed: {}", e));
+                0
+            }
+        };
+        if let Some(e) = app.conn.protocol_error() {
+            app.report_connection_error(describe_wayland_error(&WaylandError::Protocol(e)));
+        }
         let _ = self.count_sender.send(Some(count));
         app.take_wayland_events()
     }
 }
 
+fn describe_wayland_error(error: &WaylandError) -> String {
+    match error {
+        WaylandError::Protocol(e) => format!(
+            "Wayland protocol error {} on {}@{}: {}",
+            e.code, e.object_interface, e.object_id, e.message
+        ),
+        WaylandError::Io(e) => format!("Wayland connection error: {}", e),
+    }
+}
+
 // This doesn't work properly because it is
 // more likely that the locking thread is stuck at prepare_read or
 // read_without_dispatch, then the signaling here won't be received until those
@@ -562,6 +1393,7 @@ impl LayerShellHandler for Application {
         configure: LayerSurfaceConfigure,
         _serial: u32,
     ) {
+        // SCTK has already acked the serial with `zwlr_layer_surface_v1.ack_configure`
         self.push_wayland_event(WaylandEvent::LayerShellConfigure(
             target_layer.clone(),
             configure.clone(),
@@ -577,6 +1409,7 @@ impl PopupHandler for Application {
         target_popup: &Popup,
         config: PopupConfigure,
     ) {
+        // SCTK has already acked the serial with `xdg_surface.ack_configure`
         self.push_wayland_event(WaylandEvent::PopupConfigure(
             target_popup.clone(),
             config.clone(),
@@ -606,6 +1439,7 @@ impl WindowHandler for Application {
         configure: WindowConfigure,
         _serial: u32,
     ) {
+        // SCTK has already acked the serial with `xdg_surface.ack_configure`
         self.push_wayland_event(WaylandEvent::WindowConfigure(
             target_window.clone(),
             configure.clone(),
@@ -629,9 +1463,24 @@ impl PointerHandler for Application {
                 PointerEventKind::Enter { serial } => {
                     self.last_pointer_enter_serial = Some(serial);
                     self.last_pointer = Some(pointer.clone());
+                    self.pointer_entered(event.surface.clone());
+                }
+                PointerEventKind::Leave { .. } => {
+                    self.pointer_state.surface = None;
+                    self.pointer_state.buttons.clear();
+                }
+                PointerEventKind::Press { serial, button, .. } => {
+                    self.last_pointer_button_serial = Some(serial);
+                    if !self.pointer_state.buttons.contains(&button) {
+                        self.pointer_state.buttons.push(button);
+                    }
+                }
+                PointerEventKind::Release { button, .. } => {
+                    self.pointer_state.buttons.retain(|held| *held != button);
                 }
                 _ => {}
             }
+            self.pointer_state.position = event.position;
 
             self.push_wayland_event(WaylandEvent::PointerEvent((
                 event.surface.clone(),
@@ -710,6 +1559,7 @@ impl KeyboardHandler for Application {
         _layout: u32,
     ) {
         self.push_wayland_event(WaylandEvent::ModifiersChanged(modifiers.clone()));
+        self.pointer_state.modifiers = modifiers;
     }
 
     fn repeat_key(
@@ -723,6 +1573,18 @@ impl KeyboardHandler for Application {
         trace!("[MAIN] Key repeated: keycode={}", event.raw_code);
         self.push_wayland_event(WaylandEvent::KeyRepeat(event.clone()));
     }
+
+    fn update_repeat_info(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _keyboard: &WlKeyboard,
+        info: RepeatInfo,
+    ) {
+        trace!("[MAIN] Keyboard repeat info: {:?}", info);
+        self.keyboard_repeat_info = Some(info);
+        self.push_wayland_event(WaylandEvent::KeyboardRepeatInfo(info));
+    }
 }
 
 impl SeatHandler for Application {
@@ -760,8 +1622,37 @@ impl SeatHandler for Application {
             }
         }
         if capability == Capability::Pointer {
-            let _ = self.seat_state.get_pointer(&qh, &seat);
-            trace!("[MAIN] Creating themed pointer");
+            let pointer = if self.cursor_shape_manager.is_some() {
+                self.seat_state.get_pointer(qh, &seat)
+            } else {
+                trace!("[MAIN] Creating themed pointer");
+                // ThemeSpec isn't Clone, each seat's pointer takes its own
+                let theme = match &self.cursor_theme {
+                    ThemeSpec::Named { name, size } => ThemeSpec::Named { name, size: *size },
+                    ThemeSpec::System => ThemeSpec::System,
+                };
+                self.seat_state
+                    .get_pointer_with_theme(
+                        qh,
+                        &seat,
+                        self.shm_state.wl_shm(),
+                        self.compositor_state.create_surface(qh),
+                        theme,
+                    )
+                    .map(|themed_pointer| {
+                        let pointer = themed_pointer.pointer().clone();
+                        self.themed_pointer = Some(themed_pointer);
+                        pointer
+                    })
+            };
+            self.pointer_seat = Some(seat.clone());
+            if let Ok(pointer) = pointer
+                && let Some(gestures) = self.pointer_gestures.as_ref()
+                && let Ok(gestures) = gestures.get()
+            {
+                gestures.get_pinch_gesture(&pointer, qh, ());
+                trace!("[MAIN] Created zwp_pointer_gesture_pinch_v1");
+            }
         }
     }
 
@@ -875,6 +1766,76 @@ impl Dispatch<ZwpTextInputV3, ()> for Application {
     }
 }
 
+impl Dispatch<ZwpPointerGesturePinchV1, ()> for Application {
+    fn event(
+        state: &mut Application,
+        _pinch: &ZwpPointerGesturePinchV1,
+        evt: zwp_pointer_gesture_pinch_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match evt {
+            zwp_pointer_gesture_pinch_v1::Event::Begin { surface, .. } => {
+                trace!("[COMMON] Pinch begin for surface {:?}", surface.id());
+                state.pinch_scale = 1.0;
+                state.pinch_surface = Some(surface.clone());
+                state.push_wayland_event(WaylandEvent::PinchBegin(surface));
+            }
+            zwp_pointer_gesture_pinch_v1::Event::Update {
+                scale, rotation, ..
+            } => {
+                // Compositor sends the scale relative to the beginning of the
+                // gesture, convert it to a change since the last update
+                if let Some(surface) = state.pinch_surface.clone() {
+                    let scale_delta = if state.pinch_scale > 0.0 {
+                        scale / state.pinch_scale
+                    } else {
+                        1.0
+                    };
+                    state.pinch_scale = scale;
+                    state.push_wayland_event(WaylandEvent::PinchUpdate(
+                        surface,
+                        scale_delta,
+                        rotation,
+                    ));
+                }
+            }
+            zwp_pointer_gesture_pinch_v1::Event::End { cancelled, .. } => {
+                if let Some(surface) = state.pinch_surface.take() {
+                    trace!("[COMMON] Pinch end for surface {:?}", surface.id());
+                    state.push_wayland_event(WaylandEvent::PinchEnd(surface, cancelled != 0));
+                }
+            }
+            _ => {
+                trace!("[COMMON] Received unhandled pinch gesture event: {:?}", evt);
+            }
+        }
+    }
+}
+
+impl Dispatch<WpFractionalScaleV1, WlSurface> for Application {
+    fn event(
+        state: &mut Application,
+        _: &WpFractionalScaleV1,
+        evt: wp_fractional_scale_v1::Event,
+        surface: &WlSurface,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wp_fractional_scale_v1::Event::PreferredScale { scale } = evt {
+            // Scale is sent in 120ths
+            let scale = scale as f64 / 120.0;
+            trace!(
+                "[COMMON] Preferred fractional scale {} for surface {:?}",
+                scale,
+                surface.id()
+            );
+            state.push_wayland_event(WaylandEvent::FractionalScaleChanged(surface.clone(), scale));
+        }
+    }
+}
+
 impl Dispatch<WpViewport, ()> for Application {
     fn event(
         _: &mut Application,
@@ -888,6 +1849,32 @@ impl Dispatch<WpViewport, ()> for Application {
     }
 }
 
+impl Dispatch<XdgDialogV1, ()> for Application {
+    fn event(
+        _: &mut Application,
+        _: &XdgDialogV1,
+        _: xdg_dialog_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // No events expected from xdg_dialog_v1
+    }
+}
+
+impl Dispatch<ZwpIdleInhibitorV1, ()> for Application {
+    fn event(
+        _: &mut Application,
+        _: &ZwpIdleInhibitorV1,
+        _: zwp_idle_inhibitor_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // No events expected from zwp_idle_inhibitor_v1
+    }
+}
+
 impl Dispatch<WlRegion, ()> for Application {
     fn event(
         _state: &mut Self,
@@ -900,6 +1887,18 @@ impl Dispatch<WlRegion, ()> for Application {
     }
 }
 
+impl ActivationHandler for Application {
+    type RequestData = RequestData;
+
+    fn new_token(&mut self, token: String, data: &Self::RequestData) {
+        if let (Some(activation), Some(surface)) = (&self.activation, &data.surface) {
+            trace!("[MAIN] Activating surface {:?}", surface.id());
+            activation.activate::<Self>(surface, token);
+        }
+    }
+}
+
+delegate_activation!(Application);
 delegate_compositor!(Application);
 delegate_subcompositor!(Application);
 delegate_output!(Application);
@@ -914,6 +1913,10 @@ delegate_xdg_popup!(Application);
 delegate_registry!(Application);
 delegate_simple!(Application, WpViewporter, 1);
 delegate_simple!(Application, ZwpTextInputManagerV3, 1);
+delegate_simple!(Application, ZwpPointerGesturesV1, 1);
+delegate_simple!(Application, WpFractionalScaleManagerV1, 1);
+delegate_simple!(Application, XdgWmDialogV1, 1);
+delegate_simple!(Application, ZwpIdleInhibitManagerV1, 1);
 
 // ----------------------------------------------------------------
 // Request frame helper
@@ -952,3 +1955,158 @@ delegate_simple!(Application, ZwpTextInputManagerV3, 1);
 //         self.commit();
 //     }
 // }
+
+/// Font added after the default fonts of `family`, so they still win for
+/// the glyphs they have
+pub(crate) fn font_insert(
+    name: String,
+    bytes: Vec<u8>,
+    family: egui::FontFamily,
+) -> egui::epaint::text::FontInsert {
+    egui::epaint::text::FontInsert::new(
+        &name,
+        egui::FontData::from_owned(bytes),
+        vec![egui::epaint::text::InsertFontFamily {
+            family,
+            priority: egui::epaint::text::FontPriority::Lowest,
+        }],
+    )
+}
+
+fn ui_scale_from_env() -> f32 {
+    parse_ui_scale(std::env::var("WAYAPP_UI_SCALE").ok().as_deref())
+}
+
+/// UI scale of a `WAYAPP_UI_SCALE` value, `1.0` if unset or invalid
+fn parse_ui_scale(value: Option<&str>) -> f32 {
+    value
+        .and_then(|value| value.trim().parse::<f32>().ok())
+        .filter(|scale| scale.is_finite() && *scale > 0.0)
+        .unwrap_or(1.0)
+}
+
+/// XCursor icon of a cursor shape, for the themed pointer fallback
+fn shape_to_cursor_icon(shape: Shape) -> CursorIcon {
+    match shape {
+        Shape::ContextMenu => CursorIcon::ContextMenu,
+        Shape::Help => CursorIcon::Help,
+        Shape::Pointer => CursorIcon::Pointer,
+        Shape::Progress => CursorIcon::Progress,
+        Shape::Wait => CursorIcon::Wait,
+        Shape::Cell => CursorIcon::Cell,
+        Shape::Crosshair => CursorIcon::Crosshair,
+        Shape::Text => CursorIcon::Text,
+        Shape::VerticalText => CursorIcon::VerticalText,
+        Shape::Alias => CursorIcon::Alias,
+        Shape::Copy => CursorIcon::Copy,
+        Shape::Move => CursorIcon::Move,
+        Shape::NoDrop => CursorIcon::NoDrop,
+        Shape::NotAllowed => CursorIcon::NotAllowed,
+        Shape::Grab => CursorIcon::Grab,
+        Shape::Grabbing => CursorIcon::Grabbing,
+        Shape::EResize => CursorIcon::EResize,
+        Shape::NResize => CursorIcon::NResize,
+        Shape::NeResize => CursorIcon::NeResize,
+        Shape::NwResize => CursorIcon::NwResize,
+        Shape::SResize => CursorIcon::SResize,
+        Shape::SeResize => CursorIcon::SeResize,
+        Shape::SwResize => CursorIcon::SwResize,
+        Shape::WResize => CursorIcon::WResize,
+        Shape::EwResize => CursorIcon::EwResize,
+        Shape::NsResize => CursorIcon::NsResize,
+        Shape::NeswResize => CursorIcon::NeswResize,
+        Shape::NwseResize => CursorIcon::NwseResize,
+        Shape::ColResize => CursorIcon::ColResize,
+        Shape::RowResize => CursorIcon::RowResize,
+        Shape::AllScroll => CursorIcon::AllScroll,
+        Shape::ZoomIn => CursorIcon::ZoomIn,
+        Shape::ZoomOut => CursorIcon::ZoomOut,
+        _ => CursorIcon::Default,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::os::unix::net::UnixStream;
+    use wayland_client::protocol::wl_compositor::WlCompositor;
+    use wayland_client::protocol::wl_registry::WlRegistry;
+
+    struct NoCompositor;
+    wayland_client::delegate_noop!(NoCompositor: ignore WlRegistry);
+    wayland_client::delegate_noop!(NoCompositor: WlCompositor);
+    wayland_client::delegate_noop!(NoCompositor: ignore WlSurface);
+
+    /// Surfaces on a socket nobody reads, the requests are never flushed
+    pub(crate) fn surfaces<const N: usize>() -> [WlSurface; N] {
+        let (socket, _) = UnixStream::pair().unwrap();
+        let conn = Connection::from_socket(socket).unwrap();
+        let queue = conn.new_event_queue::<NoCompositor>();
+        let registry = conn.display().get_registry(&queue.handle(), ());
+        let compositor: WlCompositor = registry.bind(1, 1, &queue.handle(), ());
+        std::array::from_fn(|_| compositor.create_surface(&queue.handle(), ()))
+    }
+
+    #[test]
+    fn grouped_events_merge_global_events_in_order() {
+        let [a, b, c] = surfaces();
+        let events = vec![
+            WaylandEvent::ImeDone(1),
+            WaylandEvent::ScheduledFrame(a.clone()),
+            WaylandEvent::ScheduledFrame(b.clone()),
+            WaylandEvent::ImeDone(2),
+            WaylandEvent::KeyboardLeave(a.clone()),
+            WaylandEvent::ImeDone(3),
+        ];
+        let grouped = GroupedWaylandEvents::from(events);
+        let describe = |events: SurfaceEvents| {
+            events
+                .map(|event| match event {
+                    WaylandEvent::ImeDone(serial) => format!("global {serial}"),
+                    WaylandEvent::ScheduledFrame(surface) if *surface == a => "a frame".into(),
+                    WaylandEvent::ScheduledFrame(surface) if *surface == b => "b frame".into(),
+                    WaylandEvent::KeyboardLeave(surface) if *surface == a => "a leave".into(),
+                    event => panic!("Unexpected {event:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            describe(grouped.for_surface(&a)),
+            ["global 1", "a frame", "global 2", "a leave", "global 3"]
+        );
+        assert_eq!(
+            describe(grouped.for_surface(&b)),
+            ["global 1", "b frame", "global 2", "global 3"]
+        );
+        // A surface without events of its own gets only the global ones
+        assert_eq!(
+            describe(grouped.for_surface(&c)),
+            ["global 1", "global 2", "global 3"]
+        );
+        assert_eq!(grouped.global().count(), 3);
+        assert_eq!(grouped.surfaces().count(), 2);
+    }
+
+    #[test]
+    fn ui_scale_parses_positive_numbers() {
+        assert_eq!(parse_ui_scale(Some("1.5")), 1.5);
+        assert_eq!(parse_ui_scale(Some(" 2 ")), 2.0);
+        assert_eq!(parse_ui_scale(Some("0.75")), 0.75);
+    }
+
+    #[test]
+    fn ui_scale_defaults_to_one() {
+        for value in [
+            None,
+            Some(""),
+            Some("abc"),
+            Some("0"),
+            Some("-1"),
+            Some("inf"),
+            Some("NaN"),
+        ] {
+            assert_eq!(parse_ui_scale(value), 1.0, "{value:?}");
+        }
+    }
+}
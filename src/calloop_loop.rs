@@ -0,0 +1,101 @@
+//! Calloop integration
+//!
+//! Alternative to `Application::run_dispatcher`: the Wayland connection is
+//! read by a calloop event loop on the main thread instead of a reader thread.
+//!
+//! Create the application with a dispatch function that pings the loop, so
+//! that events from `WaylandEventEmitter` and the frame scheduler wake it up:
+//!
+//! ```ignore
+//! let mut event_loop = EventLoop::<Application>::try_new()?;
+//! let (ping, ping_source) = make_ping()?;
+//! event_loop.handle().insert_source(ping_source, |_, _, _| {})?;
+//! let mut app = Application::new(move |_| ping.ping());
+//! app.insert_into_loop(&event_loop.handle())?;
+//! loop {
+//!     event_loop.dispatch(None, &mut app)?;
+//!     let events = app.take_wayland_events();
+//!     // ... handle events
+//! }
+//! ```
+
+use crate::Application;
+use log::trace;
+use smithay_client_toolkit::reexports::calloop;
+use smithay_client_toolkit::reexports::calloop::LoopHandle;
+use smithay_client_toolkit::reexports::calloop::PostAction;
+use smithay_client_toolkit::reexports::calloop::RegistrationToken;
+use smithay_client_toolkit::reexports::calloop::generic::Generic;
+use smithay_client_toolkit::reexports::calloop::timer::TimeoutAction;
+use smithay_client_toolkit::reexports::calloop::timer::Timer;
+use smithay_client_toolkit::reexports::calloop_wayland_source::WaylandSource;
+use std::os::fd::AsFd;
+use std::time::Duration;
+
+impl Application {
+    /// Register the Wayland connection as a source in the calloop event loop
+    ///
+    /// Use this instead of `run_dispatcher`. Each time the loop dispatches,
+    /// pending Wayland events are dispatched to the application, take them
+    /// with `take_wayland_events` after `EventLoop::dispatch` returns.
+    pub fn insert_into_loop(
+        &mut self,
+        handle: &LoopHandle<'static, Application>,
+    ) -> Result<RegistrationToken, calloop::Error> {
+        trace!("[COMMON] Inserting Wayland source into calloop");
+        let event_queue = self.take_event_queue();
+        WaylandSource::new(self.conn.clone(), event_queue)
+            .insert(handle.clone())
+            .map_err(|e| e.error)
+    }
+}
+
+/// Helpers for registering timers and file descriptors to a calloop loop
+/// driving the application
+pub trait ApplicationLoopHandleExt {
+    /// Call `callback` after `delay`, and again after the returned duration
+    ///
+    /// Returning `None` removes the timer.
+    fn insert_timer(
+        &self,
+        delay: Duration,
+        callback: impl FnMut(&mut Application) -> Option<Duration> + 'static,
+    ) -> Result<RegistrationToken, calloop::Error>;
+
+    /// Call `callback` whenever `fd` is readable
+    fn insert_fd<F: AsFd + 'static>(
+        &self,
+        fd: F,
+        callback: impl FnMut(&F, &mut Application) + 'static,
+    ) -> Result<RegistrationToken, calloop::Error>;
+}
+
+impl ApplicationLoopHandleExt for LoopHandle<'static, Application> {
+    fn insert_timer(
+        &self,
+        delay: Duration,
+        mut callback: impl FnMut(&mut Application) -> Option<Duration> + 'static,
+    ) -> Result<RegistrationToken, calloop::Error> {
+        self.insert_source(
+            Timer::from_duration(delay),
+            move |_, _, app| match callback(app) {
+                Some(next) => TimeoutAction::ToDuration(next),
+                None => TimeoutAction::Drop,
+            },
+        )
+        .map_err(|e| e.error)
+    }
+
+    fn insert_fd<F: AsFd + 'static>(
+        &self,
+        fd: F,
+        mut callback: impl FnMut(&F, &mut Application) + 'static,
+    ) -> Result<RegistrationToken, calloop::Error> {
+        let source = Generic::new(fd, calloop::Interest::READ, calloop::Mode::Level);
+        self.insert_source(source, move |_, fd, app| {
+            callback(fd, app);
+            Ok(PostAction::Continue)
+        })
+        .map_err(|e| e.error)
+    }
+}
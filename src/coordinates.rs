@@ -0,0 +1,291 @@
+//! Coordinate spaces of a surface
+//!
+//! - Surface-local: logical pixels of the surface, used by pointer events and
+//!   input regions
+//! - Buffer-local: pixels of the attached buffer, used by buffer damage
+//! - EGUI: points EGUI lays out in, the layout size shrunk by the zoom
+//!
+//! The viewport scales the buffer to the surface, and the buffer transform may
+//! rotate or flip it, so buffer-local coordinates aren't just surface-local
+//! ones times the scale. `SurfaceCoordinates` does the conversions in one
+//! place.
+
+use crate::LogicalSize;
+use crate::PhysicalSize;
+use crate::Scale;
+use wayland_client::protocol::wl_output::Transform;
+
+/// Converts points and rectangles between the coordinate spaces of a surface
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceCoordinates {
+    /// Size of the surface, the viewport destination
+    pub surface_size: LogicalSize,
+    /// Size the content is laid out to, differs from `surface_size` with a
+    /// fixed logical resolution
+    pub layout_size: LogicalSize,
+    /// Buffer pixels per layout pixel
+    pub scale: Scale,
+    /// EGUI points per layout pixel are `1 / zoom`
+    pub zoom: f32,
+    /// Buffer transform set with `wl_surface.set_buffer_transform`
+    pub transform: Transform,
+}
+
+impl Default for SurfaceCoordinates {
+    fn default() -> Self {
+        Self {
+            surface_size: LogicalSize::new(256, 256),
+            layout_size: LogicalSize::new(256, 256),
+            scale: Scale::ONE,
+            zoom: 1.0,
+            transform: Transform::Normal,
+        }
+    }
+}
+
+impl SurfaceCoordinates {
+    /// Size of the EGUI screen in points
+    pub fn egui_size(&self) -> egui::Vec2 {
+        let layout = self.layout_size;
+        egui::vec2(layout.width as f32, layout.height as f32) / self.zoom
+    }
+
+    /// Size of the buffer, width and height are swapped by 90° transforms
+    pub fn buffer_size(&self) -> PhysicalSize {
+        let size = self.layout_size.to_physical(self.scale);
+        if swaps_axes(self.transform) {
+            PhysicalSize::new(size.height, size.width)
+        } else {
+            size
+        }
+    }
+
+    /// Surface-local point, e.g. a pointer position, to EGUI points
+    pub fn surface_to_egui(&self, x: f64, y: f64) -> egui::Pos2 {
+        let (sx, sy) = self.layout_per_surface();
+        egui::pos2((x * sx) as f32 / self.zoom, (y * sy) as f32 / self.zoom)
+    }
+
+    /// EGUI point to surface-local coordinates, e.g. for input regions
+    pub fn egui_to_surface(&self, pos: egui::Pos2) -> (f64, f64) {
+        let (sx, sy) = self.layout_per_surface();
+        (
+            (pos.x * self.zoom) as f64 / sx,
+            (pos.y * self.zoom) as f64 / sy,
+        )
+    }
+
+    /// Surface-local point to buffer-local coordinates
+    pub fn surface_to_buffer(&self, x: f64, y: f64) -> (f64, f64) {
+        let (sx, sy) = self.buffer_per_surface();
+        let size = self.layout_size.to_physical(self.scale);
+        transform_point(
+            self.transform,
+            size.width as f64,
+            size.height as f64,
+            x * sx,
+            y * sy,
+        )
+    }
+
+    /// Buffer-local point to surface-local coordinates
+    pub fn buffer_to_surface(&self, x: f64, y: f64) -> (f64, f64) {
+        let (sx, sy) = self.buffer_per_surface();
+        let size = self.buffer_size();
+        let (x, y) = transform_point(
+            inverse(self.transform),
+            size.width as f64,
+            size.height as f64,
+            x,
+            y,
+        );
+        (x / sx, y / sy)
+    }
+
+    /// EGUI rectangle to a surface-local `(x, y, width, height)`, rounded
+    /// outwards, e.g. for `wl_region.add`
+    pub fn egui_rect_to_surface(&self, rect: egui::Rect) -> (i32, i32, i32, i32) {
+        let min = self.egui_to_surface(rect.min);
+        let max = self.egui_to_surface(rect.max);
+        bounding_box([min, max])
+    }
+
+    /// EGUI rectangle to a buffer-local `(x, y, width, height)`, rounded
+    /// outwards, e.g. for `wl_surface.damage_buffer`
+    pub fn egui_rect_to_buffer(&self, rect: egui::Rect) -> (i32, i32, i32, i32) {
+        let corners = [rect.min, rect.max].map(|pos| {
+            let (x, y) = self.egui_to_surface(pos);
+            self.surface_to_buffer(x, y)
+        });
+        bounding_box(corners)
+    }
+
+    fn layout_per_surface(&self) -> (f64, f64) {
+        let layout = self.layout_size.at_least_one();
+        let surface = self.surface_size.at_least_one();
+        (
+            layout.width as f64 / surface.width as f64,
+            layout.height as f64 / surface.height as f64,
+        )
+    }
+
+    /// Buffer pixels per surface pixel before the transform
+    fn buffer_per_surface(&self) -> (f64, f64) {
+        let (sx, sy) = self.layout_per_surface();
+        let scale = self.scale.get() as f64;
+        (sx * scale, sy * scale)
+    }
+}
+
+fn swaps_axes(transform: Transform) -> bool {
+    matches!(
+        transform,
+        Transform::_90 | Transform::_270 | Transform::Flipped90 | Transform::Flipped270
+    )
+}
+
+fn inverse(transform: Transform) -> Transform {
+    match transform {
+        Transform::_90 => Transform::_270,
+        Transform::_270 => Transform::_90,
+        transform => transform,
+    }
+}
+
+/// Apply a buffer transform to a point of a `width` x `height` area, the
+/// same mapping compositors use from surface to buffer coordinates
+fn transform_point(transform: Transform, width: f64, height: f64, x: f64, y: f64) -> (f64, f64) {
+    match transform {
+        Transform::_90 => (height - y, x),
+        Transform::_180 => (width - x, height - y),
+        Transform::_270 => (y, width - x),
+        Transform::Flipped => (width - x, y),
+        Transform::Flipped90 => (height - y, width - x),
+        Transform::Flipped180 => (x, height - y),
+        Transform::Flipped270 => (y, x),
+        _ => (x, y),
+    }
+}
+
+fn bounding_box(corners: [(f64, f64); 2]) -> (i32, i32, i32, i32) {
+    let [(x0, y0), (x1, y1)] = corners;
+    let left = x0.min(x1).floor() as i32;
+    let top = y0.min(y1).floor() as i32;
+    let right = x0.max(x1).ceil() as i32;
+    let bottom = y0.max(y1).ceil() as i32;
+    (left, top, right - left, bottom - top)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coordinates(transform: Transform) -> SurfaceCoordinates {
+        SurfaceCoordinates {
+            surface_size: LogicalSize::new(100, 50),
+            layout_size: LogicalSize::new(100, 50),
+            scale: Scale::new(2.0),
+            zoom: 1.0,
+            transform,
+        }
+    }
+
+    #[test]
+    fn buffer_size_swaps_axes() {
+        assert_eq!(
+            coordinates(Transform::Normal).buffer_size(),
+            PhysicalSize::new(200, 100)
+        );
+        assert_eq!(
+            coordinates(Transform::_180).buffer_size(),
+            PhysicalSize::new(200, 100)
+        );
+        for transform in [Transform::_90, Transform::_270, Transform::Flipped90] {
+            assert_eq!(
+                coordinates(transform).buffer_size(),
+                PhysicalSize::new(100, 200)
+            );
+        }
+    }
+
+    #[test]
+    fn surface_to_buffer_rotates() {
+        assert_eq!(
+            coordinates(Transform::Normal).surface_to_buffer(10.0, 5.0),
+            (20.0, 10.0)
+        );
+        assert_eq!(
+            coordinates(Transform::_90).surface_to_buffer(10.0, 5.0),
+            (90.0, 20.0)
+        );
+        assert_eq!(
+            coordinates(Transform::_270).surface_to_buffer(10.0, 5.0),
+            (10.0, 180.0)
+        );
+        assert_eq!(
+            coordinates(Transform::Flipped90).surface_to_buffer(10.0, 5.0),
+            (90.0, 180.0)
+        );
+    }
+
+    #[test]
+    fn pointer_maps_at_fractional_scale() {
+        let coordinates = SurfaceCoordinates {
+            scale: Scale::new(1.5),
+            ..coordinates(Transform::Normal)
+        };
+        assert_eq!(coordinates.buffer_size(), PhysicalSize::new(150, 75));
+        // Surface-local pointer positions are EGUI points at any scale, the
+        // buffer pixel under the pointer is 1.5 times further
+        assert_eq!(
+            coordinates.surface_to_egui(33.5, 20.25),
+            egui::pos2(33.5, 20.25)
+        );
+        assert_eq!(coordinates.surface_to_buffer(33.5, 20.25), (50.25, 30.375));
+        assert_eq!(
+            coordinates.egui_to_surface(egui::pos2(33.5, 20.25)),
+            (33.5, 20.25)
+        );
+        assert_eq!(
+            coordinates.egui_rect_to_buffer(egui::Rect::from_min_max(
+                egui::pos2(1.0, 1.0),
+                egui::pos2(3.0, 3.0)
+            )),
+            (1, 1, 4, 4)
+        );
+    }
+
+    #[test]
+    fn zoom_shrinks_egui_points() {
+        let coordinates = SurfaceCoordinates {
+            zoom: 1.5,
+            ..coordinates(Transform::Normal)
+        };
+        assert_eq!(coordinates.egui_size(), egui::vec2(100.0 / 1.5, 50.0 / 1.5));
+        assert_eq!(
+            coordinates.surface_to_egui(30.0, 15.0),
+            egui::pos2(20.0, 10.0)
+        );
+        assert_eq!(
+            coordinates.egui_to_surface(egui::pos2(20.0, 10.0)),
+            (30.0, 15.0)
+        );
+        // The buffer covers the surface regardless of the zoom
+        assert_eq!(coordinates.buffer_size(), PhysicalSize::new(200, 100));
+    }
+
+    #[test]
+    fn buffer_to_surface_round_trips() {
+        for transform in [Transform::_90, Transform::Flipped90, Transform::_270] {
+            let coordinates = coordinates(transform);
+            for (x, y) in [(0.0, 0.0), (10.0, 5.0), (99.0, 49.0), (100.0, 50.0)] {
+                let (bx, by) = coordinates.surface_to_buffer(x, y);
+                assert_eq!(
+                    coordinates.buffer_to_surface(bx, by),
+                    (x, y),
+                    "{transform:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! AccessKit bridge for screen readers
+//!
+//! EGUI builds an AccessKit tree of its widgets when AccessKit is enabled on
+//! the context. The tree updates are forwarded to AT-SPI with `accesskit_unix`,
+//! so e.g. Orca can read the widgets, and actions requested by the screen
+//! reader are fed back to EGUI as input events.
+//!
+//! AccessKit is only enabled once an assistive technology asks for the tree,
+//! until then EGUI doesn't build it. Wayland doesn't tell clients where their
+//! surfaces are, so the window bounds reported to AT-SPI are left unset.
+
+use crate::WaylandToEguiInput;
+use accesskit_unix::Adapter;
+use egui::Context;
+use egui::accesskit::ActionHandler;
+use egui::accesskit::ActionRequest;
+use egui::accesskit::ActivationHandler;
+use egui::accesskit::DeactivationHandler;
+use egui::accesskit::TreeUpdate;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+type ScheduleFrame = Arc<dyn Fn(Duration) + Send + Sync>;
+
+/// Enables AccessKit when the screen reader requests the initial tree
+///
+/// Runs on the adapter's thread, so the tree is sent with the next frame.
+struct Activation {
+    active: Arc<AtomicBool>,
+    schedule_frame: ScheduleFrame,
+}
+
+impl ActivationHandler for Activation {
+    fn request_initial_tree(&mut self) -> Option<TreeUpdate> {
+        self.active.store(true, Ordering::Relaxed);
+        (self.schedule_frame)(Duration::ZERO);
+        None
+    }
+}
+
+struct Actions {
+    sender: Sender<ActionRequest>,
+    schedule_frame: ScheduleFrame,
+}
+
+impl ActionHandler for Actions {
+    fn do_action(&mut self, request: ActionRequest) {
+        let _ = self.sender.send(request);
+        (self.schedule_frame)(Duration::ZERO);
+    }
+}
+
+struct Deactivation {
+    active: Arc<AtomicBool>,
+}
+
+impl DeactivationHandler for Deactivation {
+    fn deactivate_accessibility(&mut self) {
+        self.active.store(false, Ordering::Relaxed);
+    }
+}
+
+/// AT-SPI adapter of an `EguiSurfaceState`
+pub(crate) struct AccessKitBridge {
+    adapter: Adapter,
+    active: Arc<AtomicBool>,
+    enabled: bool,
+    actions: Receiver<ActionRequest>,
+}
+
+impl AccessKitBridge {
+    pub fn new(schedule_frame: impl Fn(Duration) + Send + Sync + 'static) -> Self {
+        let schedule_frame: ScheduleFrame = Arc::new(schedule_frame);
+        let active = Arc::new(AtomicBool::new(false));
+        let (sender, actions) = std::sync::mpsc::channel();
+        let adapter = Adapter::new(
+            Activation {
+                active: active.clone(),
+                schedule_frame: schedule_frame.clone(),
+            },
+            Actions {
+                sender,
+                schedule_frame,
+            },
+            Deactivation {
+                active: active.clone(),
+            },
+        );
+        Self {
+            adapter,
+            active,
+            enabled: false,
+            actions,
+        }
+    }
+
+    /// Enable or disable AccessKit on the context and pass the actions
+    /// requested by the screen reader to EGUI, call before running EGUI
+    pub fn begin_frame(&mut self, egui_context: &Context, input: &mut WaylandToEguiInput) {
+        let active = self.active.load(Ordering::Relaxed);
+        if active != self.enabled {
+            log::trace!("[EGUI] AccessKit active: {}", active);
+            self.enabled = active;
+            if active {
+                egui_context.enable_accesskit();
+            } else {
+                egui_context.disable_accesskit();
+            }
+        }
+        for request in self.actions.try_iter() {
+            input.handle_accesskit_action(request);
+        }
+    }
+
+    /// Send the tree update of a frame to the screen reader
+    pub fn update(&mut self, update: Option<TreeUpdate>) {
+        if let Some(update) = update {
+            self.adapter.update_if_active(|| update);
+        }
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.adapter.update_window_focus_state(focused);
+    }
+}
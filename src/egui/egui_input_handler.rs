@@ -3,6 +3,9 @@
 //! This module provides a ViewManager-based approach to handling EGUI surfaces
 //! following the pattern from single_color.rs
 
+use crate::BufferedEvent;
+use crate::EventBuffer;
+use crate::SurfaceCoordinates;
 use egui::Event;
 use egui::ImeEvent;
 use egui::Key;
@@ -14,59 +17,120 @@ use log::trace;
 use smithay_client_toolkit::seat::keyboard::KeyEvent;
 use smithay_client_toolkit::seat::keyboard::Keysym;
 use smithay_client_toolkit::seat::keyboard::Modifiers as WaylandModifiers;
+use smithay_client_toolkit::seat::pointer::AxisScroll;
 use smithay_client_toolkit::seat::pointer::PointerEvent;
 use smithay_client_toolkit::seat::pointer::PointerEventKind;
 use smithay_clipboard::Clipboard;
 use std::time::Instant;
 use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::Shape;
 
+/// Time of the latest frame callback, see
+/// `WaylandToEguiInput::handle_frame_time`
+struct CompositorClock {
+    /// Seconds since the start of the input handler
+    callback_time: f64,
+    last_callback: Option<u32>,
+    received: Instant,
+}
+
+impl Default for CompositorClock {
+    fn default() -> Self {
+        Self {
+            callback_time: 0.0,
+            last_callback: None,
+            received: Instant::now(),
+        }
+    }
+}
+
 /// Handles input events from Wayland and converts them to EGUI RawInput
 pub struct WaylandToEguiInput {
     modifiers: EguiModifiers,
     pointer_pos: Pos2,
-    events: Vec<Event>,
-    screen_width: u32,
-    screen_height: u32,
+    coordinates: SurfaceCoordinates,
+    events: EventBuffer<Event>,
     start_time: Instant,
-    clipboard: Clipboard,
+    compositor_clock: Option<CompositorClock>,
+    last_time: f64,
+    clipboard: Option<Clipboard>,
     last_key_utf8: Option<String>,
     has_keyboard_focus: bool,
+    button_map: [PointerButton; egui::NUM_POINTER_BUTTONS],
+    settings: InputSettings,
 }
 
 impl WaylandToEguiInput {
     pub fn new(clipboard: Clipboard) -> Self {
+        Self::with_clipboard(Some(clipboard))
+    }
+
+    /// Without a clipboard copy and paste do nothing, e.g. in tests which
+    /// have no Wayland connection
+    fn with_clipboard(clipboard: Option<Clipboard>) -> Self {
         Self {
             modifiers: EguiModifiers::default(),
             pointer_pos: Pos2::ZERO,
-            events: Vec::new(),
-            screen_width: 256,
-            screen_height: 256,
+            coordinates: SurfaceCoordinates::default(),
+            events: EventBuffer::new(crate::DEFAULT_MAX_BUFFERED_EVENTS),
             start_time: Instant::now(),
+            compositor_clock: None,
+            last_time: 0.0,
             clipboard,
             last_key_utf8: None,
             has_keyboard_focus: false,
+            button_map: IDENTITY_BUTTON_MAP,
+            settings: InputSettings::default(),
         }
     }
 
-    pub fn set_screen_size(&mut self, width: u32, height: u32) {
-        self.screen_width = width;
-        self.screen_height = height;
+    /// Remap pointer buttons, `map[button as usize]` is the button EGUI gets
+    ///
+    /// E.g. swap `Primary` and `Secondary` for a left-handed setting of the
+    /// app, when the compositor doesn't do it already. Defaults to identity.
+    pub fn set_button_map(&mut self, map: [PointerButton; egui::NUM_POINTER_BUTTONS]) {
+        self.button_map = map;
+    }
+
+    /// Set the scroll and pointer sensitivity, see `InputSettings`
+    pub fn set_input_settings(&mut self, settings: InputSettings) {
+        self.settings = settings;
+    }
+
+    pub fn input_settings(&self) -> InputSettings {
+        self.settings
+    }
+
+    fn map_button(&self, button: u32) -> Option<PointerButton> {
+        let button = wayland_button_to_egui(button)?;
+        Some(self.button_map[button as usize])
+    }
+
+    /// Coordinate spaces of the surface, sets the screen rect and maps
+    /// pointer positions to EGUI points
+    pub fn set_coordinates(&mut self, coordinates: SurfaceCoordinates) {
+        self.coordinates = coordinates;
     }
 
     pub fn handle_pointer_event(&mut self, event: &PointerEvent) {
-        match &event.kind {
+        self.handle_pointer(event.position, &event.kind);
+    }
+
+    /// Pointer event at a surface-local position
+    fn handle_pointer(&mut self, position: (f64, f64), kind: &PointerEventKind) {
+        match kind {
             PointerEventKind::Enter { .. } => {}
             PointerEventKind::Leave { .. } => {
-                self.events.push(Event::PointerGone);
+                self.push_event(Event::PointerGone);
             }
             PointerEventKind::Motion { .. } => {
-                let (x, y) = event.position;
-                self.pointer_pos = Pos2::new(x as f32, y as f32);
-                self.events.push(Event::PointerMoved(self.pointer_pos));
+                let (x, y) = position;
+                let pos = self.coordinates.surface_to_egui(x, y);
+                self.pointer_pos = (pos.to_vec2() * self.settings.pointer_scale).to_pos2();
+                self.push_event(Event::PointerMoved(self.pointer_pos));
             }
             PointerEventKind::Press { button, .. } => {
-                if let Some(egui_button) = wayland_button_to_egui(*button) {
-                    self.events.push(Event::PointerButton {
+                if let Some(egui_button) = self.map_button(*button) {
+                    self.push_event(Event::PointerButton {
                         pos: self.pointer_pos,
                         button: egui_button,
                         pressed: true,
@@ -75,8 +139,8 @@ impl WaylandToEguiInput {
                 }
             }
             PointerEventKind::Release { button, .. } => {
-                if let Some(egui_button) = wayland_button_to_egui(*button) {
-                    self.events.push(Event::PointerButton {
+                if let Some(egui_button) = self.map_button(*button) {
+                    self.push_event(Event::PointerButton {
                         pos: self.pointer_pos,
                         button: egui_button,
                         pressed: false,
@@ -88,47 +152,116 @@ impl WaylandToEguiInput {
                 horizontal,
                 vertical,
                 ..
-            } => {
-                let scroll_delta = egui::vec2(
-                    horizontal.discrete as f32 * 10.0,
-                    vertical.discrete as f32 * 10.0,
-                );
-                if scroll_delta != egui::Vec2::ZERO {
-                    self.events.push(Event::MouseWheel {
-                        phase: egui::TouchPhase::End,
-                        unit: egui::MouseWheelUnit::Line,
-                        delta: scroll_delta,
-                        modifiers: self.modifiers,
-                    });
-                }
-            }
+            } => self.handle_axis(horizontal, vertical),
+        }
+    }
+
+    /// Scroll of a wheel or touchpad, wheels scroll by lines and touchpads by
+    /// points
+    fn handle_axis(&mut self, horizontal: &AxisScroll, vertical: &AxisScroll) {
+        let direction = if self.settings.natural_scrolling {
+            -1.0
+        } else {
+            1.0
+        };
+        let line_speed = self.settings.scroll_line_speed * direction;
+        let line_delta = egui::vec2(
+            wheel_steps(horizontal.value120, horizontal.discrete) * line_speed,
+            wheel_steps(vertical.value120, vertical.discrete) * line_speed,
+        );
+        // Touchpads scroll continuously without wheel steps
+        let pixel_speed = self.settings.scroll_pixel_speed * direction;
+        let pixel_delta = egui::vec2(
+            horizontal.absolute as f32 * pixel_speed,
+            vertical.absolute as f32 * pixel_speed,
+        );
+        if line_delta != egui::Vec2::ZERO {
+            self.push_event(Event::MouseWheel {
+                phase: egui::TouchPhase::End,
+                unit: egui::MouseWheelUnit::Line,
+                delta: line_delta,
+                modifiers: self.modifiers,
+            });
+        } else if pixel_delta != egui::Vec2::ZERO {
+            self.push_event(Event::MouseWheel {
+                phase: egui::TouchPhase::Move,
+                unit: egui::MouseWheelUnit::Point,
+                delta: pixel_delta,
+                modifiers: self.modifiers,
+            });
+        } else if horizontal.stop || vertical.stop {
+            // Fingers lifted from a touchpad, ends kinetic scrolling
+            self.push_event(Event::MouseWheel {
+                phase: egui::TouchPhase::End,
+                unit: egui::MouseWheelUnit::Point,
+                delta: egui::Vec2::ZERO,
+                modifiers: self.modifiers,
+            });
         }
     }
 
-    pub fn handle_keyboard_enter(&mut self) {
+    pub fn handle_pinch_zoom(&mut self, scale_delta: f64) {
+        if scale_delta.is_finite() && scale_delta > 0.0 && scale_delta != 1.0 {
+            self.push_event(Event::Zoom(scale_delta as f32));
+        }
+    }
+
+    /// Action requested by a screen reader, e.g. clicking a button
+    #[cfg(feature = "accesskit")]
+    pub fn handle_accesskit_action(&mut self, request: egui::accesskit::ActionRequest) {
+        trace!("[INPUT] AccessKit action: {:?}", request.action);
+        self.push_event(Event::AccessKitActionRequest(request));
+    }
+
+    /// Keyboard focus gained, `keysyms` are the keys already held down
+    pub fn handle_keyboard_enter(&mut self, keysyms: &[Keysym]) {
         self.has_keyboard_focus = true;
-        self.events.push(Event::WindowFocused(true));
+        self.push_event(Event::WindowFocused(true));
+
+        // Seed modifiers from held keys, the compositor's modifiers event may
+        // come only after the next change
+        for keysym in keysyms {
+            match *keysym {
+                Keysym::Shift_L | Keysym::Shift_R => self.modifiers.shift = true,
+                Keysym::Control_L | Keysym::Control_R => {
+                    self.modifiers.ctrl = true;
+                    self.modifiers.command = true;
+                }
+                Keysym::Alt_L | Keysym::Alt_R => self.modifiers.alt = true,
+                _ => {}
+            }
+        }
+        // Other held keys aren't sent as presses, e.g. the Escape that closed
+        // the previous window would trigger `key_pressed(Escape)` here
     }
 
     pub fn handle_keyboard_leave(&mut self) {
         self.has_keyboard_focus = false;
-        self.events.push(Event::WindowFocused(false));
+        self.push_event(Event::WindowFocused(false));
     }
 
-    pub fn handle_keyboard_event(&mut self, event: &KeyEvent, pressed: bool, is_repeat: bool) {
+    /// Returns `false` if the key has no EGUI key mapping
+    pub fn handle_keyboard_event(
+        &mut self,
+        event: &KeyEvent,
+        pressed: bool,
+        is_repeat: bool,
+    ) -> bool {
         if pressed && !is_repeat && self.modifiers.ctrl {
             match event.keysym {
-                Keysym::c => self.events.push(Event::Copy),
-                Keysym::x => self.events.push(Event::Cut),
-                Keysym::v => self
-                    .events
-                    .push(Event::Paste(self.clipboard.load().unwrap_or_default())),
+                Keysym::c => self.push_event(Event::Copy),
+                Keysym::x => self.push_event(Event::Cut),
+                Keysym::v => {
+                    let text = self.clipboard.as_ref().and_then(|c| c.load().ok());
+                    self.push_event(Event::Paste(text.unwrap_or_default()))
+                }
                 _ => (),
             }
         }
 
-        if let Some(key) = keysym_to_egui_key(event.keysym) {
-            self.events.push(Event::Key {
+        let key = keysym_to_egui_key(event.keysym);
+        if let Some(key) = key {
+            self.push_event(Event::Key {
                 key,
                 physical_key: None,
                 pressed,
@@ -149,7 +282,7 @@ impl WaylandToEguiInput {
             }
             if let Some(text) = text {
                 if !text.chars().any(|c| c.is_control()) {
-                    self.events.push(Event::Text(text.clone()));
+                    self.push_event(Event::Text(text.clone()));
                 }
             }
         }
@@ -157,6 +290,8 @@ impl WaylandToEguiInput {
         if event.utf8.is_some() {
             self.last_key_utf8 = event.utf8.clone();
         }
+
+        key.is_some()
     }
 
     pub fn update_modifiers(&mut self, wayland_mods: &WaylandModifiers) {
@@ -169,14 +304,66 @@ impl WaylandToEguiInput {
         };
     }
 
+    /// Limit the events buffered until `take_raw_input`, see
+    /// `Application::set_max_buffered_events`
+    pub fn set_max_buffered_events(&mut self, max: usize) {
+        self.events.set_max(max);
+    }
+
+    fn push_event(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    /// Derive EGUI's time from the compositor's frame callback timestamps
+    /// instead of the monotonic clock, see `handle_frame_time`
+    pub fn set_use_compositor_clock(&mut self, enabled: bool) {
+        self.compositor_clock = enabled.then(CompositorClock::default);
+    }
+
+    /// Timestamp of a frame callback in milliseconds
+    ///
+    /// The epoch of the timestamps is arbitrary, so only the differences
+    /// between callbacks are used, added to the time of the first one. The
+    /// 32-bit millisecond timestamp wraps around every ~49.7 days, which the
+    /// wrapping difference handles.
+    pub fn handle_frame_time(&mut self, time: u32) {
+        let now = self.start_time.elapsed().as_secs_f64();
+        if let Some(clock) = &mut self.compositor_clock {
+            clock.callback_time = match clock.last_callback {
+                Some(last) => clock.callback_time + time.wrapping_sub(last) as f64 / 1000.0,
+                None => now,
+            };
+            clock.last_callback = Some(time);
+            clock.received = Instant::now();
+        }
+    }
+
+    /// Seconds since the start for `RawInput::time`
+    ///
+    /// With the compositor clock, frames between callbacks (e.g. from the
+    /// frame scheduler) advance from the latest callback time by the elapsed
+    /// monotonic time. The time never goes backwards.
+    fn time(&mut self) -> f64 {
+        let time = match &self.compositor_clock {
+            Some(clock) if clock.last_callback.is_some() => {
+                clock.callback_time + clock.received.elapsed().as_secs_f64()
+            }
+            _ => self.start_time.elapsed().as_secs_f64(),
+        };
+        self.last_time = self.last_time.max(time);
+        self.last_time
+    }
+
     pub fn take_raw_input(&mut self) -> RawInput {
-        let events = std::mem::take(&mut self.events);
+        // EGUI takes ownership of the events, so they're moved to a new Vec
+        // (none when empty), the buffer keeps its allocation
+        let events = self.events.take();
         RawInput {
             screen_rect: Some(egui::Rect::from_min_size(
                 Pos2::ZERO,
-                egui::vec2(self.screen_width as f32, self.screen_height as f32),
+                self.coordinates.egui_size(),
             )),
-            time: Some(self.start_time.elapsed().as_secs_f64()),
+            time: Some(self.time()),
             predicted_dt: 1.0 / 60.0,
             modifiers: self.modifiers,
             events,
@@ -190,7 +377,9 @@ impl WaylandToEguiInput {
     pub fn handle_output_command(&mut self, output: &egui::OutputCommand) {
         match output {
             egui::OutputCommand::CopyText(text) => {
-                self.clipboard.store(text.clone());
+                if let Some(clipboard) = &self.clipboard {
+                    clipboard.store(text.clone());
+                }
             }
             egui::OutputCommand::CopyImage(_image) => {
                 // Handle image copy if needed
@@ -199,6 +388,13 @@ impl WaylandToEguiInput {
             }
             egui::OutputCommand::OpenUrl(url) => {
                 trace!("[INPUT] OpenUrl command received: {}", url.url);
+                #[cfg(feature = "portal")]
+                crate::open_url(&url.url);
+            }
+            // Commands added in newer EGUI versions are ignored until supported
+            #[allow(unreachable_patterns)]
+            _ => {
+                trace!("[INPUT] Unsupported output command: {:?}", output);
             }
         }
     }
@@ -215,13 +411,12 @@ impl WaylandToEguiInput {
 
     pub fn handle_ime_commit(&mut self, text: &str) {
         trace!("[INPUT] Committing IME text: {}", text);
-        self.events
-            .push(Event::Ime(ImeEvent::Commit(text.to_string())));
+        self.push_event(Event::Ime(ImeEvent::Commit(text.to_string())));
     }
 
     pub fn handle_ime_preedit_string(&mut self, text: &str, _cursor_begin: i32, _cursor_end: i32) {
         trace!("[INPUT] IME preedit: {:?}", text);
-        self.events.push(Event::Ime(ImeEvent::Preedit {
+        self.push_event(Event::Ime(ImeEvent::Preedit {
             text: text.to_string(),
             active_range_chars: None,
         }));
@@ -257,16 +452,98 @@ impl WaylandToEguiInput {
     }
 }
 
-fn wayland_button_to_egui(button: u32) -> Option<PointerButton> {
-    // Linux button codes (from linux/input-event-codes.h)
+impl BufferedEvent for Event {
+    fn is_motion(&self) -> bool {
+        matches!(self, Event::PointerMoved(_))
+    }
+}
+
+/// Scroll wheel steps of an axis event, fractional for high resolution wheels
+///
+/// `value120` is 120 per detent (wl_pointer v8), so a value of 60 is half a
+/// step. Older compositors only send the integer `discrete`.
+fn wheel_steps(value120: i32, discrete: i32) -> f32 {
+    if value120 != 0 {
+        value120 as f32 / 120.0
+    } else {
+        discrete as f32
+    }
+}
+
+/// Scroll and pointer sensitivity of `WaylandToEguiInput`
+///
+/// Wayland doesn't tell which device an event came from, so the settings
+/// apply to all pointers over the surface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputSettings {
+    /// Scroll distance per mouse wheel step, in EGUI lines. Defaults to `10.0`.
+    pub scroll_line_speed: f32,
+    /// Factor of continuous (touchpad) scrolling in logical pixels. Defaults
+    /// to `1.0`.
+    pub scroll_pixel_speed: f32,
+    /// Invert the scroll direction of wheels and touchpads
+    pub natural_scrolling: bool,
+    /// Factor of pointer positions, `1.0` keeps EGUI's pointer under the
+    /// cursor
+    pub pointer_scale: f32,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            scroll_line_speed: 10.0,
+            scroll_pixel_speed: 1.0,
+            natural_scrolling: false,
+            pointer_scale: 1.0,
+        }
+    }
+}
+
+/// Button map that keeps every button as is, see `set_button_map`
+pub const IDENTITY_BUTTON_MAP: [PointerButton; egui::NUM_POINTER_BUTTONS] = [
+    PointerButton::Primary,
+    PointerButton::Secondary,
+    PointerButton::Middle,
+    PointerButton::Extra1,
+    PointerButton::Extra2,
+];
+
+// Linux button codes (from linux/input-event-codes.h)
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+const BTN_SIDE: u32 = 0x113;
+const BTN_EXTRA: u32 = 0x114;
+const BTN_FORWARD: u32 = 0x115;
+const BTN_BACK: u32 = 0x116;
+
+/// Convert a Wayland (Linux evdev) button code to an EGUI button
+///
+/// Mice report the back/forward thumb buttons either as side/extra or as
+/// back/forward, both map to `Extra1`/`Extra2`.
+pub fn wayland_button_to_egui(button: u32) -> Option<PointerButton> {
     match button {
-        0x110 => Some(PointerButton::Primary),
-        0x111 => Some(PointerButton::Secondary),
-        0x112 => Some(PointerButton::Middle),
+        BTN_LEFT => Some(PointerButton::Primary),
+        BTN_RIGHT => Some(PointerButton::Secondary),
+        BTN_MIDDLE => Some(PointerButton::Middle),
+        BTN_SIDE | BTN_BACK => Some(PointerButton::Extra1),
+        BTN_EXTRA | BTN_FORWARD => Some(PointerButton::Extra2),
         _ => None,
     }
 }
 
+/// Convert an EGUI button to a Wayland (Linux evdev) button code, e.g. for
+/// synthesizing pointer events
+pub fn egui_button_to_wayland(button: PointerButton) -> u32 {
+    match button {
+        PointerButton::Primary => BTN_LEFT,
+        PointerButton::Secondary => BTN_RIGHT,
+        PointerButton::Middle => BTN_MIDDLE,
+        PointerButton::Extra1 => BTN_SIDE,
+        PointerButton::Extra2 => BTN_EXTRA,
+    }
+}
+
 fn keysym_to_egui_key(keysym: Keysym) -> Option<Key> {
     Some(match keysym {
         // Commands:
@@ -378,8 +655,14 @@ fn keysym_to_egui_key(keysym: Keysym) -> Option<Key> {
         Keysym::F33 => Key::F33,
         Keysym::F34 => Key::F34,
         Keysym::F35 => Key::F35,
+        // Clipboard keys:
+        Keysym::XF86_Copy => Key::Copy,
+        Keysym::XF86_Cut => Key::Cut,
+        Keysym::XF86_Paste => Key::Paste,
         // Navigation keys:
-        // Keysym::BrowserBack => Key::BrowserBack,
+        Keysym::XF86_Back => Key::BrowserBack,
+        // Print, Pause, Scroll_Lock etc. have no EGUI key, see
+        // `EguiSurfaceState::set_on_unmapped_key`
         _ => return None,
     })
 }
@@ -426,3 +709,247 @@ pub fn egui_to_cursor_shape(cursor: egui::CursorIcon) -> Shape {
         ZoomOut => C::ZoomOut,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wheel(value120: i32) -> AxisScroll {
+        AxisScroll {
+            value120,
+            ..Default::default()
+        }
+    }
+
+    fn touchpad(absolute: f64) -> AxisScroll {
+        AxisScroll {
+            absolute,
+            ..Default::default()
+        }
+    }
+
+    /// Unit and delta of the scroll events of a pass
+    fn scrolls(input: &mut WaylandToEguiInput) -> Vec<(egui::MouseWheelUnit, egui::Vec2)> {
+        input
+            .take_raw_input()
+            .events
+            .into_iter()
+            .filter_map(|event| match event {
+                Event::MouseWheel { unit, delta, .. } => Some((unit, delta)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn wheel_scrolls_lines() {
+        let mut input = WaylandToEguiInput::with_clipboard(None);
+        input.handle_axis(&AxisScroll::default(), &wheel(60));
+        assert_eq!(
+            scrolls(&mut input),
+            [(egui::MouseWheelUnit::Line, egui::vec2(0.0, 5.0))]
+        );
+    }
+
+    #[test]
+    fn wheel_scroll_is_scaled_and_inverted() {
+        let mut input = WaylandToEguiInput::with_clipboard(None);
+        input.set_input_settings(InputSettings {
+            scroll_line_speed: 3.0,
+            natural_scrolling: true,
+            ..Default::default()
+        });
+        input.handle_axis(&wheel(120), &wheel(-240));
+        assert_eq!(
+            scrolls(&mut input),
+            [(egui::MouseWheelUnit::Line, egui::vec2(-3.0, 6.0))]
+        );
+    }
+
+    #[test]
+    fn touchpad_scrolls_points() {
+        let mut input = WaylandToEguiInput::with_clipboard(None);
+        input.set_input_settings(InputSettings {
+            scroll_pixel_speed: 2.0,
+            ..Default::default()
+        });
+        input.handle_axis(&touchpad(1.5), &touchpad(-4.0));
+        assert_eq!(
+            scrolls(&mut input),
+            [(egui::MouseWheelUnit::Point, egui::vec2(3.0, -8.0))]
+        );
+
+        input.set_input_settings(InputSettings {
+            natural_scrolling: true,
+            ..Default::default()
+        });
+        input.handle_axis(&AxisScroll::default(), &touchpad(4.0));
+        assert_eq!(
+            scrolls(&mut input),
+            [(egui::MouseWheelUnit::Point, egui::vec2(0.0, -4.0))]
+        );
+    }
+
+    #[test]
+    fn touchpad_stop_ends_scrolling() {
+        let mut input = WaylandToEguiInput::with_clipboard(None);
+        let stop = AxisScroll {
+            stop: true,
+            ..Default::default()
+        };
+        input.handle_axis(&AxisScroll::default(), &stop);
+        assert_eq!(
+            scrolls(&mut input),
+            [(egui::MouseWheelUnit::Point, egui::Vec2::ZERO)]
+        );
+    }
+
+    #[test]
+    fn motion_and_axis_are_one_pass() {
+        let mut input = WaylandToEguiInput::with_clipboard(None);
+        // One `wl_pointer.frame` of a scroll while moving
+        input.handle_pointer((10.0, 20.0), &PointerEventKind::Motion { time: 1 });
+        input.handle_pointer(
+            (10.0, 20.0),
+            &PointerEventKind::Axis {
+                time: 1,
+                horizontal: AxisScroll::default(),
+                vertical: wheel(120),
+                source: None,
+            },
+        );
+        let events = input.take_raw_input().events;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0], Event::PointerMoved(egui::pos2(10.0, 20.0)));
+        assert!(matches!(
+            events[1],
+            Event::MouseWheel {
+                unit: egui::MouseWheelUnit::Line,
+                ..
+            }
+        ));
+        assert!(input.take_raw_input().events.is_empty());
+    }
+
+    #[test]
+    fn keyboard_enter_seeds_only_modifiers() {
+        let mut input = WaylandToEguiInput::with_clipboard(None);
+        input.handle_keyboard_enter(&[Keysym::Control_L, Keysym::Escape, Keysym::a]);
+        let raw_input = input.take_raw_input();
+        assert!(raw_input.modifiers.ctrl);
+        assert!(raw_input.modifiers.command);
+        assert!(!raw_input.modifiers.shift);
+        assert!(raw_input.focused);
+        assert_eq!(raw_input.events, [Event::WindowFocused(true)]);
+    }
+
+    fn key_event(keysym: Keysym) -> KeyEvent {
+        KeyEvent {
+            time: 0,
+            raw_code: 0,
+            keysym,
+            utf8: None,
+        }
+    }
+
+    #[test]
+    fn browser_and_clipboard_keys_map_to_egui() {
+        let table = [
+            (Keysym::XF86_Back, Key::BrowserBack),
+            (Keysym::XF86_Copy, Key::Copy),
+            (Keysym::XF86_Cut, Key::Cut),
+            (Keysym::XF86_Paste, Key::Paste),
+        ];
+        for (keysym, key) in table {
+            assert_eq!(keysym_to_egui_key(keysym), Some(key), "{keysym:?}");
+
+            let mut input = WaylandToEguiInput::with_clipboard(None);
+            assert!(input.handle_keyboard_event(&key_event(keysym), true, false));
+            assert!(matches!(
+                input.take_raw_input().events[..],
+                [Event::Key { key: k, pressed: true, .. }] if k == key
+            ));
+        }
+    }
+
+    #[test]
+    fn keys_without_egui_key_are_unmapped() {
+        for keysym in [Keysym::Print, Keysym::Pause, Keysym::Scroll_Lock] {
+            assert_eq!(keysym_to_egui_key(keysym), None, "{keysym:?}");
+
+            let mut input = WaylandToEguiInput::with_clipboard(None);
+            assert!(!input.handle_keyboard_event(&key_event(keysym), true, false));
+            assert!(input.take_raw_input().events.is_empty());
+        }
+    }
+
+    #[test]
+    fn keyboard_focus_toggles_focused() {
+        let mut input = WaylandToEguiInput::with_clipboard(None);
+        assert!(!input.take_raw_input().focused);
+
+        input.handle_keyboard_enter(&[]);
+        let raw_input = input.take_raw_input();
+        assert!(raw_input.focused);
+        assert_eq!(raw_input.events, [Event::WindowFocused(true)]);
+        // Focus is kept between passes
+        assert!(input.take_raw_input().focused);
+
+        input.handle_keyboard_leave();
+        let raw_input = input.take_raw_input();
+        assert!(!raw_input.focused);
+        assert_eq!(raw_input.events, [Event::WindowFocused(false)]);
+    }
+
+    #[test]
+    fn wayland_buttons_map_to_egui() {
+        let table = [
+            (BTN_LEFT, PointerButton::Primary),
+            (BTN_RIGHT, PointerButton::Secondary),
+            (BTN_MIDDLE, PointerButton::Middle),
+            (BTN_SIDE, PointerButton::Extra1),
+            (BTN_BACK, PointerButton::Extra1),
+            (BTN_EXTRA, PointerButton::Extra2),
+            (BTN_FORWARD, PointerButton::Extra2),
+        ];
+        for (code, button) in table {
+            assert_eq!(wayland_button_to_egui(code), Some(button), "{code:#x}");
+        }
+        // BTN_TASK and codes of other devices have no EGUI button
+        assert_eq!(wayland_button_to_egui(0x117), None);
+        assert_eq!(wayland_button_to_egui(0), None);
+    }
+
+    #[test]
+    fn egui_buttons_round_trip() {
+        for button in IDENTITY_BUTTON_MAP {
+            let code = egui_button_to_wayland(button);
+            assert_eq!(wayland_button_to_egui(code), Some(button), "{button:?}");
+        }
+    }
+
+    #[test]
+    fn button_map_remaps_buttons() {
+        let mut input = WaylandToEguiInput::with_clipboard(None);
+        assert_eq!(input.map_button(BTN_LEFT), Some(PointerButton::Primary));
+        let mut map = IDENTITY_BUTTON_MAP;
+        map.swap(0, 1);
+        input.set_button_map(map);
+        assert_eq!(input.map_button(BTN_LEFT), Some(PointerButton::Secondary));
+        assert_eq!(input.map_button(BTN_RIGHT), Some(PointerButton::Primary));
+        assert_eq!(input.map_button(BTN_MIDDLE), Some(PointerButton::Middle));
+    }
+
+    #[test]
+    fn wheel_steps_are_fractional() {
+        assert_eq!(wheel_steps(120, 1), 1.0);
+        assert_eq!(wheel_steps(60, 0), 0.5);
+        assert_eq!(wheel_steps(-60, 0), -0.5);
+    }
+
+    #[test]
+    fn wheel_steps_fall_back_to_discrete() {
+        assert_eq!(wheel_steps(0, 2), 2.0);
+        assert_eq!(wheel_steps(0, 0), 0.0);
+    }
+}
@@ -0,0 +1,254 @@
+//! Stack of notifications, each in its own overlay layer surface
+//!
+//! Notifications are stacked from the anchored corner of the output, and
+//! dismissed after a timeout or when their content asks for it. The timeout is
+//! scheduled with the surface's frame scheduler, so the event loop is woken
+//! when a notification expires.
+
+use crate::Application;
+use crate::EguiSurfaceState;
+use crate::LogicalSize;
+use crate::RendererError;
+use crate::WaylandEvent;
+use log::trace;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::wlr_layer::Anchor;
+use smithay_client_toolkit::shell::wlr_layer::Layer;
+use smithay_client_toolkit::shell::wlr_layer::LayerSurface;
+use std::time::Duration;
+use std::time::Instant;
+use wayland_client::Proxy;
+
+/// Default time until a notification is dismissed
+pub const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Identifies a notification of a `NotificationStack`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NotificationId(u64);
+
+struct Notification {
+    id: NotificationId,
+    surface: EguiSurfaceState<LayerSurface>,
+    height: u32,
+    deadline: Option<Instant>,
+    content: Box<dyn FnMut(&mut egui::Ui) -> bool>,
+    dismissed: bool,
+}
+
+/// Overlay notifications stacked from a corner of the output
+pub struct NotificationStack {
+    notifications: Vec<Notification>,
+    anchor: Anchor,
+    margin: i32,
+    spacing: i32,
+    timeout: Option<Duration>,
+    next_id: u64,
+}
+
+impl Default for NotificationStack {
+    fn default() -> Self {
+        Self::new(Anchor::TOP | Anchor::RIGHT)
+    }
+}
+
+impl NotificationStack {
+    /// Stack notifications from the corner of `anchor`, e.g. `TOP | RIGHT`
+    ///
+    /// The stack grows down from top anchors and up from bottom anchors.
+    pub fn new(anchor: Anchor) -> Self {
+        Self {
+            notifications: Vec::new(),
+            anchor,
+            margin: 16,
+            spacing: 8,
+            timeout: Some(NOTIFICATION_TIMEOUT),
+            next_id: 0,
+        }
+    }
+
+    /// Set the distance from the edges of the output in logical pixels
+    pub fn set_margin(&mut self, margin: i32) {
+        self.margin = margin;
+    }
+
+    /// Set the distance between notifications in logical pixels
+    pub fn set_spacing(&mut self, spacing: i32) {
+        self.spacing = spacing;
+    }
+
+    /// Set the timeout of notifications pushed after this, `None` keeps them
+    /// until dismissed
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Show a notification
+    ///
+    /// `content` draws the notification, return `false` from it to dismiss
+    /// the notification, e.g. when its close button is clicked. Fails if the
+    /// renderer of the notification can't be created.
+    pub fn push(
+        &mut self,
+        app: &Application,
+        size: LogicalSize,
+        content: impl FnMut(&mut egui::Ui) -> bool + 'static,
+    ) -> Result<NotificationId, RendererError> {
+        let layer_surface = app.layer_shell.create_layer_surface(
+            &app.qh,
+            app.compositor_state.create_surface(&app.qh),
+            Layer::Overlay,
+            Some("notification"),
+            None,
+        );
+        layer_surface.set_anchor(self.anchor);
+        layer_surface.set_size(size.width, size.height);
+        layer_surface.set_exclusive_zone(-1);
+        let heights = self.notifications.iter().map(|n| n.height);
+        let offset = stack_offsets(self.margin, self.spacing, heights.chain([size.height]))
+            .last()
+            .unwrap_or(self.margin);
+        self.set_offset(&layer_surface, offset);
+        layer_surface.commit();
+
+        let mut surface = EguiSurfaceState::try_new(app, layer_surface, size.width, size.height)?;
+        let id = NotificationId(self.next_id);
+        self.next_id += 1;
+        surface.set_name(format!("notification {}", id.0));
+        self.notifications.push(Notification {
+            id,
+            surface,
+            height: size.height,
+            deadline: self.timeout.map(|timeout| Instant::now() + timeout),
+            content: Box::new(content),
+            dismissed: false,
+        });
+        Ok(id)
+    }
+
+    /// Dismiss a notification before its timeout
+    pub fn dismiss(&mut self, id: NotificationId) {
+        if let Some(notification) = self.notifications.iter_mut().find(|n| n.id == id) {
+            notification.dismissed = true;
+        }
+    }
+
+    /// Dismiss all notifications
+    pub fn clear(&mut self) {
+        self.notifications.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.notifications.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notifications.is_empty()
+    }
+
+    /// Render the notifications, and remove the dismissed and expired ones
+    pub fn handle_events(&mut self, app: &mut Application, events: &[WaylandEvent]) {
+        let now = Instant::now();
+        for notification in &mut self.notifications {
+            let closed = events.iter().any(|event| match event {
+                WaylandEvent::LayerShellClosed(layer) => {
+                    layer.wl_surface().id() == notification.surface.wl_surface().id()
+                }
+                _ => false,
+            });
+            let expired = notification
+                .deadline
+                .is_some_and(|deadline| deadline <= now);
+            if closed || expired {
+                notification.dismissed = true;
+                continue;
+            }
+            if let Some(deadline) = notification.deadline {
+                // Wakes the loop when the notification expires
+                notification.surface.request_frame_after(deadline - now);
+            }
+            let content = &mut notification.content;
+            let dismissed = &mut notification.dismissed;
+            notification.surface.handle_events(app, events, &mut |ui| {
+                if !content(ui) {
+                    *dismissed = true;
+                }
+            });
+        }
+
+        let count = self.notifications.len();
+        self.notifications.retain(|notification| {
+            if notification.dismissed {
+                trace!("[EGUI] Removing {}", notification.surface.name());
+            }
+            !notification.dismissed
+        });
+        if self.notifications.len() != count {
+            self.restack();
+        }
+    }
+
+    /// Move the notifications to their places in the stack
+    fn restack(&mut self) {
+        let heights: Vec<_> = self.notifications.iter().map(|n| n.height).collect();
+        let offsets = stack_offsets(self.margin, self.spacing, heights);
+        for (index, offset) in offsets.enumerate() {
+            self.set_offset(self.notifications[index].surface.get_content(), offset);
+            // Margins are applied with the next commit
+            self.notifications[index].surface.request_frame();
+        }
+    }
+
+    /// Set the distance of a notification from the anchored edge
+    fn set_offset(&self, layer_surface: &LayerSurface, offset: i32) {
+        let (top, right, bottom, left) = stack_margins(self.anchor, self.margin, offset);
+        layer_surface.set_margin(top, right, bottom, left);
+    }
+}
+
+/// Offsets from the anchored edge of notifications of `heights`, in stack
+/// order
+fn stack_offsets(
+    margin: i32,
+    spacing: i32,
+    heights: impl IntoIterator<Item = u32>,
+) -> impl Iterator<Item = i32> {
+    heights.into_iter().scan(margin, move |offset, height| {
+        let current = *offset;
+        *offset += height as i32 + spacing;
+        Some(current)
+    })
+}
+
+/// Layer surface margins (top, right, bottom, left) of a notification at
+/// `offset`, the stack grows down from top anchors and up from bottom anchors
+fn stack_margins(anchor: Anchor, margin: i32, offset: i32) -> (i32, i32, i32, i32) {
+    if anchor.contains(Anchor::BOTTOM) {
+        (0, margin, offset, margin)
+    } else {
+        (offset, margin, 0, margin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifications_restack_after_a_removal() {
+        let heights = [80, 60, 100];
+        let offsets: Vec<_> = stack_offsets(16, 8, heights).collect();
+        assert_eq!(offsets, [16, 104, 172]);
+
+        // The middle one is dismissed, the last moves up to its place
+        let offsets: Vec<_> = stack_offsets(16, 8, [80, 100]).collect();
+        assert_eq!(offsets, [16, 104]);
+        // And a pushed one goes after it
+        let pushed = stack_offsets(16, 8, [80, 100, 50]).last();
+        assert_eq!(pushed, Some(212));
+
+        let top = Anchor::TOP | Anchor::RIGHT;
+        assert_eq!(stack_margins(top, 16, 104), (104, 16, 0, 16));
+        let bottom = Anchor::BOTTOM | Anchor::LEFT;
+        assert_eq!(stack_margins(bottom, 16, 104), (0, 16, 104, 16));
+    }
+}
@@ -0,0 +1,319 @@
+//! Offscreen rendering of EGUI to images
+//!
+//! Renders a single EGUI frame to a texture without a Wayland surface, e.g. for
+//! thumbnails or visual tests in CI. The compositor is not involved at all.
+
+use crate::LogicalSize;
+use crate::PhysicalSize;
+use crate::Scale;
+use egui::Context;
+use egui::Pos2;
+use egui::RawInput;
+use egui_wgpu::Renderer;
+use egui_wgpu::RendererOptions;
+use egui_wgpu::ScreenDescriptor;
+use egui_wgpu::wgpu;
+use egui_wgpu::wgpu::StoreOp;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+// EGUI writes gamma space colors, so the texture must not be sRGB
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// Render one EGUI frame and write it to a PNG file
+///
+/// The image is `size` scaled by `scale` pixels. Colors are premultiplied by
+/// alpha, which makes no difference for opaque UIs (panels paint a background).
+pub fn render_ui_to_png(
+    path: impl AsRef<Path>,
+    size: LogicalSize,
+    scale: Scale,
+    ui: impl FnMut(&mut egui::Ui),
+) -> io::Result<()> {
+    let (physical, pixels) = render_ui_to_rgba(size, scale, ui)?;
+    let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+    write_png(&mut file, physical, &pixels)?;
+    file.flush()
+}
+
+/// Render one EGUI frame to RGBA pixels, rows top to bottom without padding
+pub fn render_ui_to_rgba(
+    size: LogicalSize,
+    scale: Scale,
+    mut ui: impl FnMut(&mut egui::Ui),
+) -> io::Result<(PhysicalSize, Vec<u8>)> {
+    let size = size.at_least_one();
+    let physical = size.to_physical(scale).at_least_one();
+
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::all(),
+        ..wgpu::InstanceDescriptor::new_without_display_handle()
+    });
+    let adapter =
+        futures::executor::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            compatible_surface: None,
+            ..Default::default()
+        }))
+        .map_err(io::Error::other)?;
+    let (device, queue) =
+        futures::executor::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            memory_hints: wgpu::MemoryHints::MemoryUsage,
+            ..Default::default()
+        }))
+        .map_err(io::Error::other)?;
+
+    // Layout settles on the second pass (e.g. sizes of windows), textures
+    // such as the font atlas are uploaded on the first
+    let egui_context = Context::default();
+    egui_context.set_pixels_per_point(scale.get());
+    let raw_input = RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(
+            Pos2::ZERO,
+            egui::vec2(size.width as f32, size.height as f32),
+        )),
+        ..Default::default()
+    };
+    let mut full_output = egui_context.run_ui(raw_input.clone(), &mut ui);
+    full_output.append(egui_context.run_ui(raw_input, &mut ui));
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("egui offscreen"),
+        size: wgpu::Extent3d {
+            width: physical.width,
+            height: physical.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    let mut egui_renderer = Renderer::new(
+        &device,
+        FORMAT,
+        RendererOptions {
+            msaa_samples: 1,
+            depth_stencil_format: None,
+            ..Default::default()
+        },
+    );
+    let screen_descriptor = ScreenDescriptor {
+        size_in_pixels: [physical.width, physical.height],
+        pixels_per_point: scale.get(),
+    };
+    let tris = egui_context.tessellate(full_output.shapes, full_output.pixels_per_point);
+    for (id, image_delta) in &full_output.textures_delta.set {
+        egui_renderer.update_texture(&device, &queue, *id, image_delta);
+    }
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    egui_renderer.update_buffers(&device, &queue, &mut encoder, &tris, &screen_descriptor);
+    let rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &texture_view,
+            resolve_target: None,
+            depth_slice: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                store: StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        label: Some("egui offscreen render pass"),
+        occlusion_query_set: None,
+        multiview_mask: None,
+    });
+    egui_renderer.render(&mut rpass.forget_lifetime(), &tris, &screen_descriptor);
+
+    // Rows of a texture copy must be aligned
+    let row_bytes = physical.width * 4;
+    let padded_row_bytes =
+        row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("egui offscreen readback"),
+        size: padded_row_bytes as u64 * physical.height as u64,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes),
+                rows_per_image: Some(physical.height),
+            },
+        },
+        wgpu::Extent3d {
+            width: physical.width,
+            height: physical.height,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    buffer
+        .slice(..)
+        .map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+    device
+        .poll(wgpu::PollType::wait_indefinitely())
+        .map_err(io::Error::other)?;
+    receiver
+        .recv()
+        .map_err(io::Error::other)?
+        .map_err(io::Error::other)?;
+
+    let mut pixels = Vec::with_capacity((row_bytes * physical.height) as usize);
+    {
+        let data = buffer.slice(..).get_mapped_range();
+        for row in data.chunks_exact(padded_row_bytes as usize) {
+            pixels.extend_from_slice(&row[..row_bytes as usize]);
+        }
+    }
+    buffer.unmap();
+
+    Ok((physical, pixels))
+}
+
+/// Write RGBA pixels as an uncompressed PNG
+fn write_png(out: &mut impl Write, size: PhysicalSize, pixels: &[u8]) -> io::Result<()> {
+    let row_bytes = size.width as usize * 4;
+
+    // Each row starts with filter type 0 (none)
+    let mut raw = Vec::with_capacity((row_bytes + 1) * size.height as usize);
+    for row in pixels.chunks_exact(row_bytes) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    // Zlib stream with stored (uncompressed) deflate blocks
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        zlib.push(blocks.peek().is_none() as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&size.width.to_be_bytes());
+    header.extend_from_slice(&size.height.to_be_bytes());
+    // 8 bits per channel, RGBA, default compression, filtering and no
+    // interlacing
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    out.write_all(b"\x89PNG\r\n\x1a\n")?;
+    write_png_chunk(out, b"IHDR", &header)?;
+    write_png_chunk(out, b"IDAT", &zlib)?;
+    write_png_chunk(out, b"IEND", &[])
+}
+
+fn write_png_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn be_u32(bytes: &[u8]) -> u32 {
+        u32::from_be_bytes(bytes[..4].try_into().unwrap())
+    }
+
+    #[test]
+    fn crc32_of_known_vectors() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(&[]), 0);
+    }
+
+    #[test]
+    fn adler32_of_known_vectors() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(&[]), 1);
+    }
+
+    #[test]
+    fn png_of_2x2_image() {
+        let pixels: Vec<u8> = (0..16).collect();
+        let mut png = Vec::new();
+        write_png(&mut png, PhysicalSize::new(2, 2), &pixels).unwrap();
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        // IHDR: 2x2, 8 bits per channel, RGBA
+        let ihdr = &png[8..];
+        assert_eq!(be_u32(ihdr), 13);
+        assert_eq!(&ihdr[4..8], b"IHDR");
+        assert_eq!(be_u32(&ihdr[8..]), 2);
+        assert_eq!(be_u32(&ihdr[12..]), 2);
+        assert_eq!(&ihdr[16..21], &[8, 6, 0, 0, 0]);
+        assert_eq!(be_u32(&ihdr[21..]), crc32(&ihdr[4..21]));
+
+        // IDAT: one final stored block of the filtered rows
+        let idat = &ihdr[25..];
+        let len = be_u32(idat) as usize;
+        assert_eq!(&idat[4..8], b"IDAT");
+        let zlib = &idat[8..8 + len];
+        let raw = [0, 0, 1, 2, 3, 4, 5, 6, 7, 0, 8, 9, 10, 11, 12, 13, 14, 15];
+        assert_eq!(&zlib[..3], &[0x78, 0x01, 1]);
+        assert_eq!(&zlib[3..7], &[18, 0, !18, 0xFF]);
+        assert_eq!(&zlib[7..25], &raw);
+        assert_eq!(be_u32(&zlib[25..]), adler32(&raw));
+        assert_eq!(len, 29);
+        assert_eq!(be_u32(&idat[8 + len..]), crc32(&idat[4..8 + len]));
+
+        // IEND closes the file
+        let iend = &idat[12 + len..];
+        assert_eq!(
+            iend,
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! EGUI layer surfaces on every output
+
+use crate::Application;
+use crate::EguiSurfaceState;
+use crate::PerOutputSurfaces;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::wlr_layer::Anchor;
+use smithay_client_toolkit::shell::wlr_layer::Layer;
+use smithay_client_toolkit::shell::wlr_layer::LayerSurface;
+use wayland_client::protocol::wl_output::WlOutput;
+
+impl Application {
+    /// Create a fullscreen EGUI layer surface on every output, e.g. for
+    /// wallpapers or overlays
+    ///
+    /// `create` makes the app state of each surface. Surfaces are created for
+    /// outputs added later and dropped with their output, when the returned
+    /// `PerOutputSurfaces` gets the events in `handle_events`.
+    pub fn create_egui_layer_per_output<A: 'static>(
+        &self,
+        layer: Layer,
+        namespace: impl Into<String>,
+        mut create: impl FnMut(&WlOutput) -> A + 'static,
+    ) -> PerOutputSurfaces<(EguiSurfaceState<LayerSurface>, A)> {
+        let namespace = namespace.into();
+        PerOutputSurfaces::new(self, move |app, output| {
+            let layer_surface = app.create_layer_surface_on(output, layer, Some(namespace.clone()));
+            layer_surface.set_anchor(Anchor::all());
+            // Zero size with all anchors stretches to the output, and -1 keeps
+            // other surfaces' exclusive zones from shrinking it
+            layer_surface.set_size(0, 0);
+            layer_surface.set_exclusive_zone(-1);
+            layer_surface.commit();
+
+            let (width, height) = app
+                .output_state
+                .info(output)
+                .and_then(|info| info.logical_size)
+                .unwrap_or((1, 1));
+            let surface = EguiSurfaceState::new(app, layer_surface, width as u32, height as u32);
+            (surface, create(output))
+        })
+    }
+}
@@ -0,0 +1,142 @@
+//! Tooltips in their own layer surface, following the pointer
+//!
+//! Unlike EGUI's tooltips, these aren't clipped to the parent surface, e.g. a
+//! rich tooltip of a thin panel can extend over the desktop below it.
+
+use crate::Application;
+use crate::EguiSurfaceState;
+use crate::LogicalSize;
+use crate::RendererError;
+use crate::WaylandEvent;
+use smithay_client_toolkit::compositor::Region;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::wlr_layer::Anchor;
+use smithay_client_toolkit::shell::wlr_layer::Layer;
+use smithay_client_toolkit::shell::wlr_layer::LayerSurface;
+use wayland_client::Proxy;
+use wayland_client::protocol::wl_surface::WlSurface;
+
+/// Offset of the tooltip from the pointer in logical pixels, clear of the
+/// cursor image
+pub const TOOLTIP_POINTER_OFFSET: (i32, i32) = (16, 16);
+
+/// Overlay layer surface following the pointer over a parent surface, see
+/// `Application::create_pointer_tooltip`
+pub struct PointerTooltip {
+    surface: Option<EguiSurfaceState<LayerSurface>>,
+    parent: WlSurface,
+    parent_origin: (i32, i32),
+    offset: (i32, i32),
+    margin: (i32, i32),
+}
+
+impl Application {
+    /// Show a tooltip next to the pointer while it's over `parent`
+    ///
+    /// Wayland doesn't tell clients where their surfaces are, so
+    /// `parent_origin` is the position of the parent on the output, e.g.
+    /// `(0, 0)` for a panel anchored to the top left. That makes this mostly
+    /// useful for layer surface parents. The tooltip doesn't take pointer or
+    /// keyboard input, and is dismissed when the pointer leaves the parent.
+    /// Fails if the renderer of the tooltip can't be created.
+    pub fn create_pointer_tooltip(
+        &self,
+        parent: &WlSurface,
+        parent_origin: (i32, i32),
+        size: LogicalSize,
+    ) -> Result<PointerTooltip, RendererError> {
+        let layer_surface = self.layer_shell.create_layer_surface(
+            &self.qh,
+            self.compositor_state.create_surface(&self.qh),
+            Layer::Overlay,
+            Some("tooltip"),
+            None,
+        );
+        layer_surface.set_anchor(Anchor::TOP | Anchor::LEFT);
+        layer_surface.set_size(size.width, size.height);
+        layer_surface.set_exclusive_zone(-1);
+
+        // Empty input region, so the pointer stays on the parent
+        match Region::new(&self.compositor_state) {
+            Ok(region) => layer_surface.set_input_region(Some(region.wl_region())),
+            Err(err) => log::warn!("[EGUI] Failed to create tooltip input region: {}", err),
+        }
+
+        let mut tooltip = PointerTooltip {
+            surface: None,
+            parent: parent.clone(),
+            parent_origin,
+            offset: TOOLTIP_POINTER_OFFSET,
+            margin: (i32::MIN, i32::MIN),
+        };
+        tooltip.update_margin(self, &layer_surface);
+        layer_surface.commit();
+
+        let surface = EguiSurfaceState::try_new(self, layer_surface, size.width, size.height)?;
+        tooltip.surface = Some(surface);
+        Ok(tooltip)
+    }
+}
+
+impl PointerTooltip {
+    /// Set the offset from the pointer, defaults to `TOOLTIP_POINTER_OFFSET`
+    pub fn set_offset(&mut self, offset: (i32, i32)) {
+        self.offset = offset;
+    }
+
+    /// Set the position of the parent on the output, e.g. after moving it
+    pub fn set_parent_origin(&mut self, origin: (i32, i32)) {
+        self.parent_origin = origin;
+    }
+
+    /// Destroy the tooltip surface
+    pub fn dismiss(&mut self) {
+        self.surface = None;
+    }
+
+    pub fn is_dismissed(&self) -> bool {
+        self.surface.is_none()
+    }
+
+    /// Follow the pointer and render the tooltip, dismisses it when the
+    /// pointer has left the parent
+    pub fn handle_events(
+        &mut self,
+        app: &mut Application,
+        events: &[WaylandEvent],
+        ui: &mut impl FnMut(&mut egui::Ui),
+    ) {
+        let over_parent = app
+            .pointer_state()
+            .surface
+            .as_ref()
+            .is_some_and(|surface| surface.id() == self.parent.id());
+        if !over_parent {
+            self.dismiss();
+        }
+        let Some(mut surface) = self.surface.take() else {
+            return;
+        };
+        if self.update_margin(app, surface.get_content()) {
+            // Margins are applied with the next commit
+            surface.request_frame();
+        }
+        surface.handle_events(app, events, ui);
+        self.surface = Some(surface);
+    }
+
+    /// Move the surface to the pointer, returns whether it moved
+    fn update_margin(&mut self, app: &Application, layer_surface: &LayerSurface) -> bool {
+        let (x, y) = app.pointer_state().position;
+        let margin = (
+            self.parent_origin.0 + x.round() as i32 + self.offset.0,
+            self.parent_origin.1 + y.round() as i32 + self.offset.1,
+        );
+        if margin == self.margin {
+            return false;
+        }
+        self.margin = margin;
+        layer_surface.set_margin(margin.1, 0, 0, margin.0);
+        true
+    }
+}
@@ -0,0 +1,47 @@
+//! Running a single EGUI window without setting up the event loop
+
+use crate::Application;
+use crate::EguiSurfaceState;
+use crate::LogicalSize;
+use crate::WaylandEvent;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
+
+impl Application {
+    /// Open a window and run `ui` in it until the window is closed
+    ///
+    /// For quick tools, like `eframe::run_simple_native`. Apps with several
+    /// surfaces or their own events create the `Application` themselves.
+    pub fn run_egui_window(title: &str, size: LogicalSize, mut ui: impl FnMut(&mut egui::Ui)) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut app = Application::new(move |token| {
+            let _ = tx.send(token);
+        });
+
+        let window = app.xdg_shell.create_window(
+            app.compositor_state.create_surface(&app.qh),
+            WindowDecorations::ServerDefault,
+            &app.qh,
+        );
+        window.set_title(title);
+        window.commit();
+
+        let mut egui_window = EguiSurfaceState::new(&app, &window, size.width, size.height);
+
+        app.run_dispatcher();
+
+        while let Ok(token) = rx.recv() {
+            let events = app.dispatch_pending(token);
+            egui_window.handle_events(&mut app, &events, &mut ui);
+            let closed = events.iter().any(|event| {
+                matches!(
+                    event,
+                    WaylandEvent::WindowRequestClose(_) | WaylandEvent::ConnectionError(_)
+                )
+            });
+            if closed {
+                break;
+            }
+        }
+    }
+}
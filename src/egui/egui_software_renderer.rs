@@ -0,0 +1,446 @@
+//! Software renderer for EGUI
+//!
+//! Fallback when WGPU can't be initialized, e.g. in CI without a GPU. EGUI's
+//! tessellated triangles are rasterized on the CPU into a shm buffer, so it's
+//! only meant for simple UIs: every frame is drawn from scratch, textures are
+//! sampled without filtering and large surfaces cost noticeable CPU time.
+
+use crate::EguiWgpuRenderer;
+use crate::PhysicalSize;
+use crate::RendererError;
+use crate::Scale;
+use egui::Color32;
+use egui::Context;
+use egui::Pos2;
+use egui::TextureId;
+use egui::epaint::ClippedPrimitive;
+use egui::epaint::Mesh;
+use egui::epaint::Primitive;
+use egui_wgpu::wgpu;
+use smithay_client_toolkit::shm::Shm;
+use smithay_client_toolkit::shm::slot::SlotPool;
+use std::collections::HashMap;
+use std::time::Duration;
+use wayland_client::protocol::wl_shm;
+use wayland_client::protocol::wl_surface::WlSurface;
+
+struct Texture {
+    size: [usize; 2],
+    pixels: Vec<Color32>,
+}
+
+impl Texture {
+    /// Nearest texel at the UV coordinates
+    fn sample(&self, uv: Pos2) -> Color32 {
+        let [width, height] = self.size;
+        let x = ((uv.x * width as f32) as usize).min(width.saturating_sub(1));
+        let y = ((uv.y * height as f32) as usize).min(height.saturating_sub(1));
+        self.pixels
+            .get(y * width + x)
+            .copied()
+            .unwrap_or(Color32::WHITE)
+    }
+
+    /// Copy `image` into the texture with its top left corner at `pos`
+    fn update(&mut self, [x, y]: [usize; 2], image: &egui::ColorImage) {
+        let [width, _] = image.size;
+        for (row, src) in image.pixels.chunks_exact(width.max(1)).enumerate() {
+            let start = (y + row) * self.size[0] + x;
+            if let Some(dst) = self.pixels.get_mut(start..start + src.len()) {
+                dst.copy_from_slice(src);
+            }
+        }
+    }
+}
+
+/// CPU renderer for EGUI drawing into shm buffers
+pub struct EguiSoftwareRenderer {
+    egui_context: Context,
+    pool: SlotPool,
+    wl_surface: WlSurface,
+    textures: HashMap<TextureId, Texture>,
+    clear_color: Color32,
+    suspended: bool,
+}
+
+impl EguiSoftwareRenderer {
+    pub fn new(
+        egui_context: &Context,
+        wl_surface: &WlSurface,
+        shm: &Shm,
+    ) -> Result<Self, RendererError> {
+        let pool = SlotPool::new(256 * 256 * 4, shm).map_err(RendererError::ShmPool)?;
+        Ok(Self {
+            egui_context: egui_context.clone(),
+            pool,
+            wl_surface: wl_surface.clone(),
+            textures: HashMap::new(),
+            clear_color: Color32::TRANSPARENT,
+            suspended: false,
+        })
+    }
+
+    /// Stop rendering, textures are kept
+    pub fn suspend(&mut self) {
+        self.suspended = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.suspended = false;
+    }
+
+    /// Set the color the surface is cleared with before EGUI draws
+    pub fn set_clear_color(&mut self, color: Color32) {
+        self.clear_color = color;
+    }
+
+    /// Rasterize EGUI output and commit it to the surface
+    pub fn render(&mut self, full_output: egui::FullOutput, size: PhysicalSize, scale: Scale) {
+        for (id, delta) in &full_output.textures_delta.set {
+            self.update_texture(*id, delta);
+        }
+
+        if !self.suspended {
+            let size = size.at_least_one();
+            let primitives = self
+                .egui_context
+                .tessellate(full_output.shapes, full_output.pixels_per_point);
+            let mut pixels = vec![self.clear_color; (size.width * size.height) as usize];
+            for ClippedPrimitive {
+                clip_rect,
+                primitive,
+            } in &primitives
+            {
+                // Paint callbacks need a GPU, they're skipped
+                if let Primitive::Mesh(mesh) = primitive {
+                    let clip = egui::Rect::from_min_max(
+                        (clip_rect.min.to_vec2() * scale.get()).floor().to_pos2(),
+                        (clip_rect.max.to_vec2() * scale.get()).ceil().to_pos2(),
+                    );
+                    let texture = self.textures.get(&mesh.texture_id);
+                    draw_mesh(&mut pixels, size, clip, mesh, texture, scale.get());
+                }
+            }
+            self.present(size, &pixels);
+        }
+
+        for id in &full_output.textures_delta.free {
+            self.textures.remove(id);
+        }
+    }
+
+    fn update_texture(&mut self, id: TextureId, delta: &egui::epaint::ImageDelta) {
+        let image = match &delta.image {
+            egui::ImageData::Color(image) => image,
+            #[allow(unreachable_patterns)]
+            _ => return,
+        };
+        match delta.pos {
+            None => {
+                self.textures.insert(
+                    id,
+                    Texture {
+                        size: image.size,
+                        pixels: image.pixels.clone(),
+                    },
+                );
+            }
+            Some(pos) => {
+                if let Some(texture) = self.textures.get_mut(&id) {
+                    texture.update(pos, image);
+                }
+            }
+        }
+    }
+
+    /// Copy the pixels to a shm buffer, attach and commit it
+    fn present(&mut self, size: PhysicalSize, pixels: &[Color32]) {
+        let (width, height) = (size.width as i32, size.height as i32);
+        let format = wl_shm::Format::Argb8888;
+        let (buffer, canvas) = match self.pool.create_buffer(width, height, width * 4, format) {
+            Ok(buffer) => buffer,
+            Err(err) => {
+                log::warn!("[EGUI] Failed to create shm buffer: {}", err);
+                return;
+            }
+        };
+
+        // Color32 is premultiplied like Argb8888, which is stored as the
+        // little-endian bytes B, G, R, A
+        for (dst, src) in canvas.chunks_exact_mut(4).zip(pixels) {
+            dst.copy_from_slice(&[src.b(), src.g(), src.r(), src.a()]);
+        }
+
+        self.wl_surface.damage_buffer(0, 0, width, height);
+        if let Err(err) = buffer.attach_to(&self.wl_surface) {
+            log::warn!("[EGUI] Failed to attach shm buffer: {}", err);
+            return;
+        }
+        self.wl_surface.commit();
+    }
+}
+
+/// Rasterize the triangles of a mesh, blending premultiplied colors
+fn draw_mesh(
+    pixels: &mut [Color32],
+    size: PhysicalSize,
+    clip: egui::Rect,
+    mesh: &Mesh,
+    texture: Option<&Texture>,
+    pixels_per_point: f32,
+) {
+    let x_min = clip.min.x.max(0.0) as i32;
+    let y_min = clip.min.y.max(0.0) as i32;
+    let x_max = (clip.max.x as i32).min(size.width as i32);
+    let y_max = (clip.max.y as i32).min(size.height as i32);
+
+    for triangle in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+        let [pa, pb, pc] = [a, b, c].map(|v| (v.pos.to_vec2() * pixels_per_point).to_pos2());
+        let [ca, cb, cc] = [a, b, c].map(|v| v.color.to_array().map(f32::from));
+        let area = edge(pa, pb, pc);
+        if area.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let left = (pa.x.min(pb.x).min(pc.x).floor() as i32).max(x_min);
+        let top = (pa.y.min(pb.y).min(pc.y).floor() as i32).max(y_min);
+        let right = (pa.x.max(pb.x).max(pc.x).ceil() as i32).min(x_max);
+        let bottom = (pa.y.max(pb.y).max(pc.y).ceil() as i32).min(y_max);
+
+        for y in top..bottom {
+            for x in left..right {
+                // Barycentric weights at the pixel center
+                let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let wa = edge(pb, pc, p) / area;
+                let wb = edge(pc, pa, p) / area;
+                let wc = 1.0 - wa - wb;
+                if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                    continue;
+                }
+
+                let mut color = [0.0f32; 4];
+                for (i, channel) in color.iter_mut().enumerate() {
+                    *channel = ca[i] * wa + cb[i] * wb + cc[i] * wc;
+                }
+                if let Some(texture) = texture {
+                    let uv =
+                        (a.uv.to_vec2() * wa + b.uv.to_vec2() * wb + c.uv.to_vec2() * wc).to_pos2();
+                    let texel = texture.sample(uv).to_array();
+                    for (channel, texel) in color.iter_mut().zip(texel) {
+                        *channel *= texel as f32 / 255.0;
+                    }
+                }
+
+                let pixel = &mut pixels[y as usize * size.width as usize + x as usize];
+                *pixel = blend(color, *pixel);
+            }
+        }
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (p.x - a.x) * (b.y - a.y) - (p.y - a.y) * (b.x - a.x)
+}
+
+/// Premultiplied source over destination
+fn blend(src: [f32; 4], dst: Color32) -> Color32 {
+    let inverse_alpha = 1.0 - src[3] / 255.0;
+    let [r, g, b, a] = dst.to_array().map(|channel| channel as f32 * inverse_alpha);
+    Color32::from_rgba_premultiplied(
+        (src[0] + r).round().min(255.0) as u8,
+        (src[1] + g).round().min(255.0) as u8,
+        (src[2] + b).round().min(255.0) as u8,
+        (src[3] + a).round().min(255.0) as u8,
+    )
+}
+
+/// GPU or software renderer of an `EguiSurfaceState`
+// One per surface, boxing the larger variant wouldn't save anything
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum SurfaceRenderer {
+    Gpu(EguiWgpuRenderer),
+    Software(EguiSoftwareRenderer),
+}
+
+impl SurfaceRenderer {
+    pub fn is_software(&self) -> bool {
+        matches!(self, SurfaceRenderer::Software(_))
+    }
+
+    pub fn suspend(&mut self) {
+        match self {
+            SurfaceRenderer::Gpu(renderer) => renderer.suspend(),
+            SurfaceRenderer::Software(renderer) => renderer.suspend(),
+        }
+    }
+
+    pub fn resume(&mut self) -> Result<(), RendererError> {
+        match self {
+            SurfaceRenderer::Gpu(renderer) => renderer.resume(),
+            SurfaceRenderer::Software(renderer) => {
+                renderer.resume();
+                Ok(())
+            }
+        }
+    }
+
+    pub fn set_clear_color(&mut self, color: Color32) {
+        match self {
+            SurfaceRenderer::Gpu(renderer) => renderer.set_clear_color(color),
+            SurfaceRenderer::Software(renderer) => renderer.set_clear_color(color),
+        }
+    }
+
+    pub fn force_reconfigure(&mut self) {
+        if let SurfaceRenderer::Gpu(renderer) = self {
+            renderer.force_reconfigure();
+        }
+    }
+
+    pub fn set_extra_surface_usages(&mut self, usages: wgpu::TextureUsages) {
+        match self {
+            SurfaceRenderer::Gpu(renderer) => renderer.set_extra_surface_usages(usages),
+            SurfaceRenderer::Software(_) => {
+                log::warn!("[EGUI] Software renderer has no swapchain, ignoring usages");
+            }
+        }
+    }
+
+    pub fn surface_usages(&self) -> wgpu::TextureUsages {
+        match self {
+            SurfaceRenderer::Gpu(renderer) => renderer.surface_usages(),
+            SurfaceRenderer::Software(_) => wgpu::TextureUsages::empty(),
+        }
+    }
+
+    /// Software rendering writes `Argb8888` shm buffers, the same byte order
+    /// as `Bgra8Unorm`
+    pub fn output_format(&self) -> wgpu::TextureFormat {
+        match self {
+            SurfaceRenderer::Gpu(renderer) => renderer.output_format(),
+            SurfaceRenderer::Software(_) => wgpu::TextureFormat::Bgra8Unorm,
+        }
+    }
+
+    pub fn set_gpu_timing_callback(
+        &mut self,
+        callback: impl FnMut(Duration) + Send + 'static,
+    ) -> bool {
+        match self {
+            SurfaceRenderer::Gpu(renderer) => renderer.set_gpu_timing_callback(callback),
+            SurfaceRenderer::Software(_) => false,
+        }
+    }
+
+    pub fn render(&mut self, full_output: egui::FullOutput, size: PhysicalSize, scale: Scale) {
+        match self {
+            SurfaceRenderer::Gpu(renderer) => renderer.render_to_wgpu(full_output, size, scale),
+            SurfaceRenderer::Software(renderer) => renderer.render(full_output, size, scale),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color32 = Color32::RED;
+    const GREEN: Color32 = Color32::GREEN;
+    const BLUE: Color32 = Color32::BLUE;
+    const WHITE: Color32 = Color32::WHITE;
+    const CLEAR: Color32 = Color32::TRANSPARENT;
+
+    fn rect_mesh(min: (f32, f32), max: (f32, f32), color: Color32) -> Mesh {
+        let mut mesh = Mesh::default();
+        mesh.add_colored_rect(
+            egui::Rect::from_min_max(Pos2::new(min.0, min.1), Pos2::new(max.0, max.1)),
+            color,
+        );
+        mesh
+    }
+
+    /// 4x4 pixels with a rectangle drawn over the clear color
+    fn draw_rect(clip: egui::Rect, color: Color32) -> Vec<Color32> {
+        let size = PhysicalSize::new(4, 4);
+        let mut pixels = vec![CLEAR; 16];
+        let mesh = rect_mesh((1.0, 1.0), (3.0, 3.0), color);
+        draw_mesh(&mut pixels, size, clip, &mesh, None, 1.0);
+        pixels
+    }
+
+    #[test]
+    fn edge_is_twice_the_signed_area() {
+        let a = Pos2::new(0.0, 0.0);
+        let b = Pos2::new(2.0, 0.0);
+        let p = Pos2::new(0.0, 2.0);
+        assert_eq!(edge(a, b, p), -4.0);
+        assert_eq!(edge(b, a, p), 4.0);
+        assert_eq!(edge(a, b, Pos2::new(1.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn blend_premultiplied_over() {
+        let white = WHITE.to_array().map(f32::from);
+        let clear = CLEAR.to_array().map(f32::from);
+        assert_eq!(blend(white, BLUE), WHITE);
+        assert_eq!(blend(clear, BLUE), BLUE);
+        assert_eq!(
+            blend([64.0, 0.0, 0.0, 128.0], WHITE),
+            Color32::from_rgba_premultiplied(191, 127, 127, 255)
+        );
+    }
+
+    #[test]
+    fn sample_nearest_texel() {
+        let texture = Texture {
+            size: [2, 2],
+            pixels: vec![RED, GREEN, BLUE, WHITE],
+        };
+        assert_eq!(texture.sample(Pos2::new(0.0, 0.0)), RED);
+        assert_eq!(texture.sample(Pos2::new(0.75, 0.25)), GREEN);
+        assert_eq!(texture.sample(Pos2::new(0.25, 0.75)), BLUE);
+        // The far edge is clamped to the last texel
+        assert_eq!(texture.sample(Pos2::new(1.0, 1.0)), WHITE);
+    }
+
+    #[test]
+    fn partial_update_keeps_other_texels() {
+        let mut texture = Texture {
+            size: [3, 3],
+            pixels: vec![RED; 9],
+        };
+        texture.update([1, 1], &egui::ColorImage::new([2, 2], vec![BLUE; 4]));
+        #[rustfmt::skip]
+        assert_eq!(texture.pixels, [
+            RED, RED, RED,
+            RED, BLUE, BLUE,
+            RED, BLUE, BLUE,
+        ]);
+    }
+
+    #[test]
+    fn mesh_covers_pixel_centers() {
+        let clip = egui::Rect::from_min_max(Pos2::ZERO, Pos2::new(4.0, 4.0));
+        #[rustfmt::skip]
+        assert_eq!(draw_rect(clip, WHITE), [
+            CLEAR, CLEAR, CLEAR, CLEAR,
+            CLEAR, WHITE, WHITE, CLEAR,
+            CLEAR, WHITE, WHITE, CLEAR,
+            CLEAR, CLEAR, CLEAR, CLEAR,
+        ]);
+    }
+
+    #[test]
+    fn mesh_is_clipped() {
+        let clip = egui::Rect::from_min_max(Pos2::ZERO, Pos2::new(2.0, 4.0));
+        #[rustfmt::skip]
+        assert_eq!(draw_rect(clip, WHITE), [
+            CLEAR, CLEAR, CLEAR, CLEAR,
+            CLEAR, WHITE, CLEAR, CLEAR,
+            CLEAR, WHITE, CLEAR, CLEAR,
+            CLEAR, CLEAR, CLEAR, CLEAR,
+        ]);
+    }
+}
@@ -3,101 +3,573 @@
 //! This module provides a ViewManager-based approach to handling EGUI surfaces
 //! following the pattern from single_color.rs
 
+#[cfg(feature = "accesskit")]
+use crate::AccessKitBridge;
 use crate::Application;
+use crate::EguiRendererOptions;
+use crate::EguiSoftwareRenderer;
 use crate::EguiWgpuRenderer;
 use crate::FrameScheduler;
+use crate::GroupedWaylandEvents;
+use crate::InputSettings;
 use crate::Kind;
+use crate::LOW_POWER_FPS;
+use crate::LogicalSize;
+use crate::PhysicalSize;
+use crate::RendererError;
+use crate::Scale;
+use crate::SharedTexture;
+use crate::SurfaceCoordinates;
+use crate::SurfaceRenderer;
+use crate::TitleBarAction;
 use crate::WaylandEvent;
 use crate::WaylandToEguiInput;
+use crate::WgpuContext;
 use crate::egui_to_cursor_shape;
+use crate::wayland_button_to_egui;
 use egui::Context;
+use log::debug;
 use log::trace;
+use smithay_client_toolkit::reexports::csd_frame::WindowManagerCapabilities;
 use smithay_client_toolkit::reexports::csd_frame::WindowState;
 use smithay_client_toolkit::seat::keyboard::KeyEvent;
+use smithay_client_toolkit::seat::keyboard::Keysym;
 use smithay_client_toolkit::seat::keyboard::Modifiers as WaylandModifiers;
 use smithay_client_toolkit::seat::pointer::PointerEvent;
+use smithay_client_toolkit::seat::pointer::PointerEventKind;
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::wlr_layer::Anchor;
+use smithay_client_toolkit::shell::wlr_layer::KeyboardInteractivity;
+use smithay_client_toolkit::shell::wlr_layer::Layer;
+use smithay_client_toolkit::shell::xdg::XdgPositioner;
+use smithay_client_toolkit::shell::xdg::XdgSurface;
+use smithay_client_toolkit::shell::xdg::popup::Popup;
+use smithay_client_toolkit::shell::xdg::window::Window;
+use smithay_client_toolkit::shell::xdg::window::WindowDecorations;
 use smithay_clipboard::Clipboard;
+use std::cell::RefCell;
 use std::num::NonZero;
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::SendError;
+use std::sync::mpsc::Sender;
 use std::time::Duration;
 use std::time::Instant;
 use wayland_client::Proxy;
+use wayland_client::protocol::wl_output::Transform;
 use wayland_client::protocol::wl_surface::WlSurface;
+use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::Shape;
+use wayland_protocols::wp::fractional_scale::v1::client::wp_fractional_scale_v1::WpFractionalScaleV1;
+use wayland_protocols::wp::idle_inhibit::zv1::client::zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1;
 use wayland_protocols::wp::viewporter::client::wp_viewport::WpViewport;
+use wayland_protocols::xdg::dialog::v1::client::xdg_dialog_v1::XdgDialogV1;
+use wayland_protocols::xdg::shell::client::xdg_positioner::Anchor as PopupAnchor;
+use wayland_protocols::xdg::shell::client::xdg_positioner::ConstraintAdjustment;
+use wayland_protocols::xdg::shell::client::xdg_positioner::Gravity;
+use wayland_protocols::xdg::shell::client::xdg_toplevel::ResizeEdge;
+
+/// How long `set_render_on_frame_only` waits for a frame callback before
+/// rendering without one
+///
+/// Compositors stop sending frame callbacks to hidden surfaces, e.g. on
+/// another workspace, which would otherwise freeze the UI until it's shown.
+pub const FRAME_CALLBACK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Placement of a popup, see `EguiSurfaceState::open_popup_with_options`
+#[derive(Debug, Clone, Copy)]
+pub struct PopupOptions {
+    /// Point of the 1x1 anchor rectangle at `pos` the popup is placed
+    /// against. Defaults to `TopLeft`.
+    pub anchor: PopupAnchor,
+    /// Direction the popup extends from the anchor. Defaults to
+    /// `BottomRight`.
+    pub gravity: Gravity,
+    /// How the compositor moves the popup when it would go off screen.
+    /// Defaults to flipping and sliding on both axes.
+    pub constraint_adjustment: ConstraintAdjustment,
+    /// Offset from the anchor in surface coordinates
+    pub offset: (i32, i32),
+}
+
+impl Default for PopupOptions {
+    fn default() -> Self {
+        Self {
+            anchor: PopupAnchor::TopLeft,
+            gravity: Gravity::BottomRight,
+            constraint_adjustment: ConstraintAdjustment::FlipX
+                | ConstraintAdjustment::FlipY
+                | ConstraintAdjustment::SlideX
+                | ConstraintAdjustment::SlideY,
+            offset: (0, 0),
+        }
+    }
+}
+
+/// Size of a deferred viewport's window when its builder has none
+const DEFAULT_VIEWPORT_SIZE: (u32, u32) = (400, 300);
+
+type ScheduleFrame = Arc<dyn Fn(Duration) + Send + Sync>;
+
+/// Viewports of an EGUI context, shared by the surface of the root viewport
+/// and the windows of its deferred viewports
+#[derive(Clone, Default)]
+struct SharedViewports {
+    /// Latest viewport output of the context, with the commands not yet
+    /// applied
+    outputs: Rc<RefCell<egui::OrderedViewportIdMap<egui::ViewportOutput>>>,
+    /// Frame schedulers of the surfaces, repaint requests are routed to the
+    /// surface of the viewport
+    schedulers: Arc<Mutex<egui::ViewportIdMap<ScheduleFrame>>>,
+}
+
+impl SharedViewports {
+    /// Replace the outputs with the ones of the latest pass, keeping the
+    /// commands not yet applied
+    fn update(&self, mut outputs: egui::OrderedViewportIdMap<egui::ViewportOutput>) {
+        let mut current = self.outputs.borrow_mut();
+        for (id, old) in std::mem::take(&mut *current) {
+            if let Some(output) = outputs.get_mut(&id) {
+                let mut commands = old.commands;
+                commands.append(&mut output.commands);
+                output.commands = commands;
+            }
+        }
+        *current = outputs;
+    }
+
+    fn take_commands(&self, id: egui::ViewportId) -> Vec<egui::ViewportCommand> {
+        self.outputs
+            .borrow_mut()
+            .get_mut(&id)
+            .map(|output| std::mem::take(&mut output.commands))
+            .unwrap_or_default()
+    }
+
+    fn schedule_frame(&self, id: egui::ViewportId, delay: Duration) {
+        if let Some(schedule_frame) = self.schedulers.lock().unwrap().get(&id) {
+            schedule_frame(delay);
+        }
+    }
+}
+
+/// Events of a whole dispatch, as given to `handle_events` or
+/// `handle_grouped_events`
+#[derive(Clone, Copy)]
+enum DispatchEvents<'a> {
+    Flat(&'a [WaylandEvent]),
+    Grouped(&'a GroupedWaylandEvents),
+}
+
+/// Window of a deferred viewport, see `ctx.show_viewport_deferred`
+struct DeferredViewport {
+    surface: EguiSurfaceState<Window>,
+    ui: Arc<egui::DeferredViewportUiCallback>,
+}
+
+/// Callback of `EguiSurfaceState::set_on_keyboard_enter`
+type KeyboardEnterCallback = Box<dyn FnMut(&Context)>;
 
 /// Surface-specific EGUI state
 pub struct EguiSurfaceState<T: Into<Kind> + Clone> {
     viewport: Option<WpViewport>,
     t: T,
     kind: Kind,
+    name: String,
     // renderer: EguiWgpuRendererThread, // for async rendering thread
-    renderer: EguiWgpuRenderer, // surface can be suspended via renderer.suspend()
+    renderer: SurfaceRenderer, // surface can be suspended via renderer.suspend()
     input_state: WaylandToEguiInput,
-    init_width: u32,
-    init_height: u32,
-    width: u32,  // WGPU Surface width in logical pixels
-    height: u32, // WGPU Surface height in logical pixels
+    size: LogicalSize,
     scale_factor: i32,
+    fps_target: f32,
+    low_power: bool,
+    fractional_scale: Option<WpFractionalScaleV1>,
+    dialog: Option<XdgDialogV1>,
+    idle_inhibitor: Option<ZwpIdleInhibitorV1>,
+    preferred_scale: Option<Scale>,
+    logical_resolution: Option<LogicalSize>,
+    layer_anchor: Anchor,
+    layer_exclusive_zone: i32,
+    /// Auto-sized bars request a size every frame, conflicts warn only once
+    layer_size_warned: bool,
     suspended: bool,
+    closed: bool,
+    render_on_frame_only: bool,
+    pause_when_unfocused: bool,
+    min_frame_interval: Option<Duration>,
+    wm_capabilities: WindowManagerCapabilities,
+    window_state: WindowState,
+    zoom: f32,
+    render_scale: f32,
+    frame_callback_requested: Option<Instant>,
     last_fulloutput: Option<egui::FullOutput>,
     last_ime_output: Option<egui::output::IMEOutput>,
     frame_timings: Option<(Instant, Instant)>,
     has_keyboard_focus: bool,
+    keyboard_interactivity: Option<KeyboardInteractivity>,
+    window_geometry: Option<(i32, i32, i32, i32)>,
+    clear_with_window_fill: bool,
+    /// Frames rendered, see `frame_number`
+    renders: u64,
     egui_context: Context,
     frame_scheduler: FrameScheduler,
+    #[cfg(feature = "accesskit")]
+    accesskit: AccessKitBridge,
+    on_keyboard_enter: Option<KeyboardEnterCallback>,
+    on_unmapped_key: Option<Box<dyn FnMut(Keysym, bool)>>,
+    on_raw_key: Option<Box<dyn FnMut(u32, bool)>>,
+    on_scale_changed: Option<Box<dyn FnMut(f32)>>,
+    on_nav_button: Option<Box<dyn FnMut(bool)>>,
+    visuals_generation: u64,
+    fonts_applied: usize,
+    textures_generation: u64,
+    redraw_generation: u64,
+    reconfigure_generation: u64,
+    title: Option<String>,
+    viewport_commands: Vec<egui::ViewportCommand>,
+    popup_position: Option<(i32, i32)>,
+    viewport_id: egui::ViewportId,
+    viewports: SharedViewports,
+    deferred_viewports: egui::ViewportIdMap<DeferredViewport>,
+    close_requested: bool,
 }
 
 impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
+    /// Create the EGUI state for a surface, panics if WGPU can't be
+    /// initialized, see `try_new`
     pub fn new(app: &Application, t: T, width: u32, height: u32) -> Self {
+        Self::try_new(app, t, width, height).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Create the EGUI state for a surface, or an error if WGPU can't be
+    /// initialized, e.g. without a GPU on CI
+    ///
+    /// Apps can then fall back to drawing with `SingleColorState` or their
+    /// own shm buffers, or use `new_with_software_fallback`.
+    pub fn try_new(
+        app: &Application,
+        t: T,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, RendererError> {
+        futures::executor::block_on(Self::try_new_async(app, t, width, height))
+    }
+
+    /// Like `new`, but awaits the WGPU adapter and device instead of blocking,
+    /// e.g. in a tokio app
+    pub async fn new_async(app: &Application, t: T, width: u32, height: u32) -> Self {
+        Self::try_new_async(app, t, width, height)
+            .await
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like `try_new`, but awaits the WGPU adapter and device instead of
+    /// blocking
+    pub async fn try_new_async(
+        app: &Application,
+        t: T,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, RendererError> {
+        Self::try_new_with_options_async(app, t, width, height, EguiRendererOptions::default())
+            .await
+    }
+
+    /// Like `try_new`, with options for the EGUI renderer, e.g. a
+    /// depth-stencil format for paint callbacks drawing a 3D scene
+    ///
+    /// Fails with `RendererError::DepthStencilFormat` if the adapter can't
+    /// render to the depth-stencil format.
+    pub fn try_new_with_options(
+        app: &Application,
+        t: T,
+        width: u32,
+        height: u32,
+        options: EguiRendererOptions,
+    ) -> Result<Self, RendererError> {
+        futures::executor::block_on(Self::try_new_with_options_async(
+            app, t, width, height, options,
+        ))
+    }
+
+    /// Like `try_new_with_options`, but awaits the WGPU adapter and device
+    /// instead of blocking
+    pub async fn try_new_with_options_async(
+        app: &Application,
+        t: T,
+        width: u32,
+        height: u32,
+        options: EguiRendererOptions,
+    ) -> Result<Self, RendererError> {
+        let egui_context = Context::default();
+        let kind: Kind = t.clone().into();
+        let mut device_options = app.device_options();
+        device_options.renderer = options;
+        let renderer = EguiWgpuRenderer::create(
+            &egui_context,
+            kind.get_wl_surface(),
+            &app.conn,
+            &device_options,
+        )
+        .await?;
+        Ok(Self::with_renderer(
+            app,
+            t,
+            width,
+            height,
+            egui_context,
+            SurfaceRenderer::Gpu(renderer),
+        ))
+    }
+
+    /// Create the EGUI state for a surface rendering on the app's own WGPU
+    /// device, see `EguiWgpuRenderer::try_with_device` for the requirements
+    ///
+    /// The GPU options of the `Application` don't apply, the device is
+    /// already created.
+    pub fn try_new_with_device(
+        app: &Application,
+        t: T,
+        width: u32,
+        height: u32,
+        gpu: &WgpuContext,
+        options: EguiRendererOptions,
+    ) -> Result<Self, RendererError> {
+        let egui_context = Context::default();
+        let kind: Kind = t.clone().into();
+        let renderer = EguiWgpuRenderer::try_with_device(
+            &egui_context,
+            kind.get_wl_surface(),
+            &app.conn,
+            gpu,
+            options,
+        )?;
+        Ok(Self::with_renderer(
+            app,
+            t,
+            width,
+            height,
+            egui_context,
+            SurfaceRenderer::Gpu(renderer),
+        ))
+    }
+
+    /// Create the EGUI state for a surface, drawing on the CPU into shm
+    /// buffers if WGPU can't be initialized
+    ///
+    /// Software rendering is slow for large or animated UIs, but keeps simple
+    /// tools working without a GPU, see `EguiSoftwareRenderer`. Panics if the
+    /// shm pool can't be created either, see `try_new_with_software_fallback`.
+    pub fn new_with_software_fallback(app: &Application, t: T, width: u32, height: u32) -> Self {
+        Self::try_new_with_software_fallback(app, t, width, height)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like `new_with_software_fallback`, but fails with
+    /// `RendererError::ShmPool` if the software renderer can't be created
+    pub fn try_new_with_software_fallback(
+        app: &Application,
+        t: T,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, RendererError> {
+        let egui_context = Context::default();
+        let kind: Kind = t.clone().into();
+        let wl_surface = kind.get_wl_surface();
+        let renderer = match futures::executor::block_on(EguiWgpuRenderer::create(
+            &egui_context,
+            wl_surface,
+            &app.conn,
+            &app.device_options(),
+        )) {
+            Ok(renderer) => SurfaceRenderer::Gpu(renderer),
+            Err(err) => {
+                log::warn!("[EGUI] {}, falling back to software rendering", err);
+                SurfaceRenderer::Software(EguiSoftwareRenderer::new(
+                    &egui_context,
+                    wl_surface,
+                    &app.shm_state,
+                )?)
+            }
+        };
+        Ok(Self::with_renderer(
+            app,
+            t,
+            width,
+            height,
+            egui_context,
+            renderer,
+        ))
+    }
+
+    fn with_renderer(
+        app: &Application,
+        t: T,
+        width: u32,
+        height: u32,
+        egui_context: Context,
+        renderer: SurfaceRenderer,
+    ) -> Self {
+        Self::with_viewport(
+            app,
+            t,
+            width,
+            height,
+            egui_context,
+            renderer,
+            egui::ViewportId::ROOT,
+            SharedViewports::default(),
+        )
+    }
+
+    /// Create the state for a viewport of `egui_context`, the root viewport
+    /// opens windows for the deferred viewports shown from its `ui()`
+    #[allow(clippy::too_many_arguments)]
+    fn with_viewport(
+        app: &Application,
+        t: T,
+        width: u32,
+        height: u32,
+        egui_context: Context,
+        renderer: SurfaceRenderer,
+        viewport_id: egui::ViewportId,
+        viewports: SharedViewports,
+    ) -> Self {
         let kind = t.clone().into();
         let wl_surface = kind.get_wl_surface();
-        let egui_context = Context::default();
-        let renderer = EguiWgpuRenderer::new(&egui_context, wl_surface, &app.conn);
         let clipboard = unsafe { Clipboard::new(app.conn.display().id().as_ptr() as *mut _) };
-        let input_state = WaylandToEguiInput::new(clipboard);
+        let mut input_state = WaylandToEguiInput::new(clipboard);
+        input_state.set_coordinates(SurfaceCoordinates {
+            zoom: app.ui_scale(),
+            ..Default::default()
+        });
         let emitter = app.get_event_emitter();
         let wl_surface_clone = wl_surface.clone();
         let frame_scheduler = FrameScheduler::new(move || {
             // Note: Using wl_surface.frame(), wl_surface.commit(), conn.flush()
             // caused crashes with WGPU handling, so I created a way to emit Frame
             // event without Wayland dispatching.
-            emitter.emit_events(vec![crate::WaylandEvent::Frame(
+            emitter.emit_events(vec![crate::WaylandEvent::ScheduledFrame(
                 wl_surface_clone.clone(),
-                0,
             )]);
         });
-        let frame_scheduler_fn = frame_scheduler.create_scheduler();
-        egui_context.set_request_repaint_callback(move |i| {
-            frame_scheduler_fn(i.delay);
-        });
+        viewports
+            .schedulers
+            .lock()
+            .unwrap()
+            .insert(viewport_id, Arc::new(frame_scheduler.create_scheduler()));
+        if viewport_id == egui::ViewportId::ROOT {
+            // Zero delay (`ctx.request_repaint()`) is clamped to the FPS target
+            // by the scheduler, so continuous animations tick at the target FPS
+            // instead of busy looping
+            let schedulers = viewports.schedulers.clone();
+            egui_context.set_request_repaint_callback(move |i| {
+                if let Some(schedule_frame) = schedulers.lock().unwrap().get(&i.viewport_id) {
+                    schedule_frame(i.delay);
+                }
+            });
+            // Deferred viewports are opened as windows sharing the WGPU
+            // renderer, the software renderer shows them embedded instead
+            egui_context.set_embed_viewports(renderer.is_software());
+        }
+
+        let name = kind.debug_name();
+
+        // With fractional scale the buffer scale stays at 1, and the viewport
+        // scales the buffer to the surface size
+        let fractional_scale = app
+            .fractional_scale_manager
+            .as_ref()
+            .and_then(|manager| manager.get().ok())
+            .map(|manager| manager.get_fractional_scale(wl_surface, &app.qh, wl_surface.clone()));
 
         Self {
             viewport: None,
             t,
             kind,
+            name,
             renderer,
             input_state,
-            init_height: height,
-            init_width: width,
-            width,
-            height,
+            size: LogicalSize::new(width, height),
             scale_factor: 1,
+            fps_target: 60.0,
+            low_power: false,
+            fractional_scale,
+            dialog: None,
+            idle_inhibitor: None,
+            preferred_scale: None,
+            logical_resolution: None,
+            layer_anchor: Anchor::empty(),
+            layer_exclusive_zone: 0,
+            layer_size_warned: false,
             suspended: false,
+            closed: false,
+            render_on_frame_only: false,
+            pause_when_unfocused: false,
+            min_frame_interval: None,
+            wm_capabilities: WindowManagerCapabilities::all(),
+            window_state: WindowState::empty(),
+            zoom: app.ui_scale(),
+            render_scale: 1.0,
+            frame_callback_requested: None,
             last_fulloutput: None,
             last_ime_output: None,
             frame_timings: None,
             has_keyboard_focus: false,
+            keyboard_interactivity: None,
+            window_geometry: None,
+            clear_with_window_fill: false,
+            renders: 0,
             egui_context,
+            #[cfg(feature = "accesskit")]
+            accesskit: AccessKitBridge::new(frame_scheduler.create_scheduler()),
             frame_scheduler,
+            on_keyboard_enter: None,
+            on_unmapped_key: None,
+            on_raw_key: None,
+            on_scale_changed: None,
+            on_nav_button: None,
+            visuals_generation: 0,
+            fonts_applied: 0,
+            textures_generation: 0,
+            redraw_generation: app.redraw_generation(),
+            reconfigure_generation: app.reconfigure_generation(),
+            title: None,
+            viewport_commands: Vec::new(),
+            popup_position: None,
+            viewport_id,
+            viewports,
+            deferred_viewports: egui::ViewportIdMap::default(),
+            close_requested: false,
         }
     }
 
+    /// Whether the surface draws with the CPU, see
+    /// `new_with_software_fallback`
+    pub fn is_software(&self) -> bool {
+        self.renderer.is_software()
+    }
+
     pub fn get_content(&self) -> &T {
         &self.t
     }
 
-    fn wl_surface(&self) -> &WlSurface {
+    /// Name of the surface used in logs
+    ///
+    /// Defaults to the kind and protocol id of the surface, e.g. `window@12`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Set the name of the surface used in logs, e.g. the window title
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    pub(crate) fn wl_surface(&self) -> &WlSurface {
         self.kind.get_wl_surface()
     }
 
@@ -112,63 +584,303 @@ impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
     fn configure(
         &mut self,
         app: &Application,
-        width: u32,
-        height: u32,
+        size: LogicalSize,
         window_state: Option<WindowState>,
     ) {
         let was_suspended = self.suspended;
-        self.resize_viewport(app, width, height);
-        self.width = width.max(1);
-        self.height = height.max(1);
-        self.input_state.set_screen_size(self.width, self.height);
+        let size = size.at_least_one();
+        debug!(
+            "[EGUI] Configuring surface {} to {}x{} at scale {}",
+            self.name,
+            size.width,
+            size.height,
+            self.scale().get()
+        );
+        self.resize_viewport(app, size);
+        self.size = size;
+        self.update_layout_size();
+        self.apply_window_geometry();
         self.suspended = window_state.map_or(false, |state| state.contains(WindowState::SUSPENDED));
 
         if self.suspended && !was_suspended {
             // Surface just got suspended — destroy the WGPU surface to free GPU
             // resources. Device, queue, and egui renderer are kept to preserve
             // texture state (EGUI doesn't handle renderer recreate well).
-            trace!(
-                "[EGUI] Suspending renderer for surface {:?}",
-                self.wl_surface().id()
-            );
+            trace!("[EGUI] Suspending renderer for surface {}", self.name);
             self.renderer.suspend();
         } else if !self.suspended && was_suspended {
             // Surface just got resumed — recreate the WGPU surface
-            trace!(
-                "[EGUI] Resuming renderer for surface {:?}",
-                self.wl_surface().id()
-            );
-            self.renderer.resume();
+            trace!("[EGUI] Resuming renderer for surface {}", self.name);
+            if let Err(err) = self.renderer.resume() {
+                log::error!("[EGUI] Failed to resume renderer of {}: {err}", self.name);
+            }
         }
 
-        self.frame_scheduler.set_fps_target(
-            if window_state.map_or(false, |state| state.contains(WindowState::SUSPENDED)) {
-                0.05 // 0.0001
-            } else {
-                60.0
-            },
+        self.apply_fps_target();
+    }
+
+    /// Frame pacing of the scheduler, from the FPS target, suspension and
+    /// the low power mode of the application
+    fn apply_fps_target(&mut self) {
+        let fps = if self.suspended {
+            0.05 // 0.0001
+        } else if self.low_power {
+            self.fps_target.min(LOW_POWER_FPS)
+        } else {
+            self.fps_target
+        };
+        self.frame_scheduler.set_fps_target(fps);
+    }
+
+    /// Set the maximum FPS for repaints, defaults to 60
+    ///
+    /// Continuous repaints, e.g. a video widget calling `ctx.request_repaint()`
+    /// every frame, are rendered at this rate.
+    pub fn set_fps_target(&mut self, fps: f32) {
+        self.fps_target = fps;
+        self.apply_fps_target();
+    }
+
+    /// Move a layer surface to another layer, e.g. an OSD from `Top` to
+    /// `Overlay` while active
+    ///
+    /// Does nothing for other than layer surfaces.
+    pub fn set_layer(&mut self, layer: Layer) {
+        let Some(layer_surface) = self.kind.as_layer_surface() else {
+            return;
+        };
+        trace!("[EGUI] Setting layer {:?} for surface {}", layer, self.name);
+        layer_surface.set_layer(layer);
+        // Layer is double buffered state, applied on commit
+        self.wl_surface().commit();
+        self.request_frame();
+    }
+
+    /// Grab all keyboard input for a layer surface, e.g. for a launcher
+    ///
+    /// `true` requests `KeyboardInteractivity::Exclusive`, `false` goes back
+    /// to `OnDemand` focus (e.g. on click). Compositors differ: some only
+    /// honor exclusive focus on the `Top` and `Overlay` layers, and some
+    /// still handle their own global bindings, so check `has_keyboard_focus`
+    /// for whether the keyboard was actually given.
+    ///
+    /// Does nothing for other than layer surfaces.
+    pub fn set_exclusive_keyboard(&mut self, exclusive: bool) {
+        let Some(layer_surface) = self.kind.as_layer_surface() else {
+            return;
+        };
+        let interactivity = if exclusive {
+            KeyboardInteractivity::Exclusive
+        } else {
+            KeyboardInteractivity::OnDemand
+        };
+        trace!(
+            "[EGUI] Setting keyboard interactivity {:?} for surface {}",
+            interactivity, self.name
         );
+        layer_surface.set_keyboard_interactivity(interactivity);
+        // Keyboard interactivity is double buffered state, applied on commit
+        self.wl_surface().commit();
+        self.keyboard_interactivity = Some(interactivity);
+    }
+
+    /// Keyboard interactivity requested with `set_exclusive_keyboard`
+    pub fn keyboard_interactivity(&self) -> Option<KeyboardInteractivity> {
+        self.keyboard_interactivity
     }
 
-    fn resize_viewport(&mut self, app: &Application, width: u32, height: u32) {
+    /// Whether the surface has keyboard focus
+    pub fn has_keyboard_focus(&self) -> bool {
+        self.has_keyboard_focus
+    }
+
+    /// Set the anchor of a layer surface, see `set_layer_size`
+    ///
+    /// Does nothing for other than layer surfaces.
+    pub fn set_layer_anchor(&mut self, anchor: Anchor) {
+        let Some(layer) = self.kind.as_layer_surface() else {
+            return;
+        };
+        layer.set_anchor(anchor);
+        self.layer_anchor = anchor;
+    }
+
+    /// Set the exclusive zone of a layer surface, see `set_layer_size`
+    ///
+    /// Does nothing for other than layer surfaces.
+    pub fn set_layer_exclusive_zone(&mut self, zone: i32) {
+        let Some(layer) = self.kind.as_layer_surface() else {
+            return;
+        };
+        layer.set_exclusive_zone(zone);
+        self.layer_exclusive_zone = zone;
+    }
+
+    /// Request a layer surface size that respects the anchoring
+    ///
+    /// A dimension anchored to both opposite edges is sized by the compositor,
+    /// and the dimension of an exclusive zone is clamped to the zone, e.g. a
+    /// top-anchored bar can grow in width but not in height. Anchor and
+    /// exclusive zone must be set with `set_layer_anchor` and
+    /// `set_layer_exclusive_zone` to be taken into account. The first conflict
+    /// with the anchoring is logged, e.g. auto-sizing sets the size every
+    /// frame.
+    ///
+    /// Does nothing for other than layer surfaces.
+    pub fn set_layer_size(&mut self, width: u32, height: u32) {
+        let Some(layer) = self.kind.as_layer_surface() else {
+            return;
+        };
+        let clamped = layer_size(self.layer_anchor, self.layer_exclusive_zone, width, height);
+        if clamped != (width, height) && !self.layer_size_warned {
+            self.layer_size_warned = true;
+            log::warn!(
+                "[EGUI] Layer size {}x{} conflicts with anchoring of surface {}, using {}x{}",
+                width,
+                height,
+                self.name,
+                clamped.0,
+                clamped.1
+            );
+        }
+        layer.set_size(clamped.0, clamped.1);
+        self.wl_surface().commit();
+    }
+
+    /// Lay out EGUI to a fixed logical resolution regardless of the surface
+    /// size, `None` lays out to the surface size (default)
+    ///
+    /// The rendered buffer is scaled to the surface by the viewport, so the
+    /// aspect ratio is not preserved. Pointer coordinates are mapped back to
+    /// the fixed resolution.
+    pub fn set_logical_resolution(&mut self, resolution: Option<LogicalSize>) {
+        self.logical_resolution = resolution.map(LogicalSize::at_least_one);
+        self.update_layout_size();
+        self.request_frame();
+    }
+
+    /// Surface size in logical pixels
+    pub fn size(&self) -> LogicalSize {
+        self.size
+    }
+
+    /// Scale from logical to physical pixels, fractional if the compositor
+    /// supports it
+    ///
+    /// Pointer coordinates are in logical pixels, which are EGUI points, so
+    /// they don't need scaling.
+    pub fn scale(&self) -> Scale {
+        self.preferred_scale
+            .unwrap_or_else(|| Scale::new(self.scale_factor.max(1) as f32))
+    }
+
+    /// EGUI pixels per point, the buffer scale times the zoom
+    fn egui_scale(&self) -> Scale {
+        Scale::new(self.buffer_scale().get() * self.zoom)
+    }
+
+    /// Buffer pixels per logical pixel, the surface scale times the render
+    /// scale
+    fn buffer_scale(&self) -> Scale {
+        Scale::new(self.scale().get() * self.render_scale)
+    }
+
+    /// Render at a fraction of the physical resolution, e.g. `0.5` for a
+    /// quarter of the pixels
+    ///
+    /// The smaller buffer is upscaled to the surface by the compositor through
+    /// the viewport, so text and edges get blurry, in exchange for less GPU
+    /// work on large surfaces such as 4K layer surfaces. Input is unaffected,
+    /// pointer positions are mapped in logical pixels. Clamped to `0.1..=1.0`,
+    /// defaults to `1.0`.
+    pub fn set_render_scale(&mut self, render_scale: f32) {
+        if !render_scale.is_finite() {
+            return;
+        }
+        let render_scale = render_scale.clamp(0.1, 1.0);
+        if render_scale == self.render_scale {
+            return;
+        }
+        self.render_scale = render_scale;
+        self.update_layout_size();
+        self.request_frame();
+    }
+
+    /// Set the zoom of the UI on top of the compositor scale, e.g. `1.5`
+    ///
+    /// Defaults to `Application::ui_scale`, which is read from
+    /// `WAYAPP_UI_SCALE`.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        if !zoom.is_finite() || zoom <= 0.0 || zoom == self.zoom {
+            return;
+        }
+        self.zoom = zoom;
+        self.update_layout_size();
+        self.request_frame();
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Size of the rendered buffer in physical pixels
+    pub fn physical_size(&self) -> PhysicalSize {
+        self.layout_size().to_physical(self.buffer_scale())
+    }
+
+    /// Size EGUI lays out to in logical pixels
+    fn layout_size(&self) -> LogicalSize {
+        self.logical_resolution.unwrap_or(self.size)
+    }
+
+    /// Coordinate spaces of the surface, for converting between pointer,
+    /// buffer and EGUI coordinates
+    pub fn coordinates(&self) -> SurfaceCoordinates {
+        SurfaceCoordinates {
+            surface_size: self.size,
+            layout_size: self.layout_size(),
+            scale: self.buffer_scale(),
+            zoom: self.zoom,
+            // The buffer transform is never set
+            transform: Transform::Normal,
+        }
+    }
+
+    fn update_layout_size(&mut self) {
+        self.input_state.set_coordinates(self.coordinates());
+    }
+
+    fn resize_viewport(&mut self, app: &Application, size: LogicalSize) {
         let wl_surface = self.wl_surface().clone();
+        let name = &self.name;
         let viewport = self.viewport.get_or_insert_with(|| {
-            trace!("[EGUI] Creating viewport for surface {:?}", wl_surface.id());
+            trace!("[EGUI] Creating viewport for surface {}", name);
             app.viewporter
                 .get()
                 .expect("wp_viewporter not available")
                 .get_viewport(&wl_surface, &app.qh, ())
         });
 
-        viewport.set_destination(width as i32, height as i32);
+        // Zero destination is a protocol error
+        let size = size.at_least_one();
+        viewport.set_destination(size.width as i32, size.height as i32);
     }
 
     fn handle_pointer_event(&mut self, event: &PointerEvent) {
         self.input_state.handle_pointer_event(event);
+        if let PointerEventKind::Press { button, .. } = event.kind
+            && let Some(on_nav_button) = &mut self.on_nav_button
+        {
+            match wayland_button_to_egui(button) {
+                Some(egui::PointerButton::Extra1) => on_nav_button(false),
+                Some(egui::PointerButton::Extra2) => on_nav_button(true),
+                _ => {}
+            }
+        }
     }
 
-    fn handle_keyboard_enter(&mut self) {
-        self.input_state.handle_keyboard_enter();
+    fn handle_keyboard_enter(&mut self, keysyms: &[Keysym]) {
+        self.input_state.handle_keyboard_enter(keysyms);
     }
 
     fn handle_keyboard_leave(&mut self) {
@@ -176,8 +888,18 @@ impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
     }
 
     fn handle_keyboard_event(&mut self, event: &KeyEvent, pressed: bool, repeat: bool) {
-        self.input_state
+        if !repeat && let Some(on_raw_key) = &mut self.on_raw_key {
+            on_raw_key(event.raw_code, pressed);
+        }
+        let mapped = self
+            .input_state
             .handle_keyboard_event(event, pressed, repeat);
+        if !mapped
+            && !repeat
+            && let Some(on_unmapped_key) = &mut self.on_unmapped_key
+        {
+            on_unmapped_key(event.keysym, pressed);
+        }
     }
 
     fn update_modifiers(&mut self, modifiers: &WaylandModifiers) {
@@ -185,24 +907,406 @@ impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
     }
 
     fn scale_factor_changed(&mut self, new_factor: i32) {
+        if self.fractional_scale.is_some() {
+            // Fractional scale takes precedence, see fractional_scale_changed
+            return;
+        }
         self.wl_surface().set_buffer_scale(new_factor);
         let factor = new_factor.max(1);
         if factor == self.scale_factor {
             return;
         }
         self.scale_factor = factor;
+        self.update_layout_size();
+    }
+
+    /// Set a callback called when the surface gains keyboard focus, before the
+    /// next EGUI frame
+    ///
+    /// Useful for focusing a text field right away, e.g. in a launcher:
+    /// `ctx.memory_mut(|m| m.request_focus(id))`
+    pub fn set_on_keyboard_enter(&mut self, callback: impl FnMut(&Context) + 'static) {
+        self.on_keyboard_enter = Some(Box::new(callback));
+    }
+
+    /// Set a callback for key presses and releases EGUI has no key for
+    ///
+    /// E.g. `Keysym::Print` for taking a screenshot, the `bool` is `true` on
+    /// press.
+    pub fn set_on_unmapped_key(&mut self, callback: impl FnMut(Keysym, bool) + 'static) {
+        self.on_unmapped_key = Some(Box::new(callback));
+    }
+
+    /// Texture format the surface renders to, see
+    /// `Application::set_prefer_10bit_color`
+    pub fn output_format(&self) -> egui_wgpu::wgpu::TextureFormat {
+        self.renderer.output_format()
+    }
+
+    /// Set a callback called when the effective scale of the surface changes
+    ///
+    /// Covers both integer buffer scale and fractional scale, and is called
+    /// before the next render, e.g. to load icons rendered for the new DPI.
+    pub fn set_on_scale_changed(&mut self, callback: impl FnMut(f32) + 'static) {
+        self.on_scale_changed = Some(Box::new(callback));
+    }
+
+    fn notify_scale_changed(&mut self, old_scale: Scale) {
+        let new_scale = self.scale();
+        if new_scale == old_scale {
+            return;
+        }
+        trace!(
+            "[EGUI] Scale of {} changed to {}",
+            self.name,
+            new_scale.get()
+        );
+        if let Some(on_scale_changed) = &mut self.on_scale_changed {
+            on_scale_changed(new_scale.get());
+        }
+    }
+
+    /// Set a callback for the back and forward mouse buttons, the `bool` is
+    /// `true` for forward
+    ///
+    /// EGUI gets them as `Extra1`/`Extra2` button events but doesn't navigate
+    /// on them, e.g. a browser-like app can go back in history here.
+    pub fn set_on_nav_button(&mut self, callback: impl FnMut(bool) + 'static) {
+        self.on_nav_button = Some(Box::new(callback));
+    }
+
+    /// Remap pointer buttons, e.g. swap `Primary` and `Secondary` for
+    /// left-handed use, see `WaylandToEguiInput::set_button_map`
+    pub fn set_button_map(&mut self, map: [egui::PointerButton; egui::NUM_POINTER_BUTTONS]) {
+        self.input_state.set_button_map(map);
+    }
+
+    /// Set the scroll speed, direction and pointer scale, see `InputSettings`
+    pub fn set_input_settings(&mut self, settings: InputSettings) {
+        self.input_state.set_input_settings(settings);
+    }
+
+    /// Set a callback for every key press and release with the evdev scancode
+    ///
+    /// The scancode is the physical key position regardless of the keyboard
+    /// layout, e.g. for WASD controls in games. It's the `wl_keyboard.key`
+    /// code as is, which is the evdev code (`KEY_W` is 17); XKB keycodes are
+    /// evdev + 8. Called before EGUI gets the key event, repeats are skipped.
+    pub fn set_on_raw_key(&mut self, callback: impl FnMut(u32, bool) + 'static) {
+        self.on_raw_key = Some(Box::new(callback));
+    }
+
+    /// Update the window title if it changed
+    ///
+    /// Meant to be called after each `handle_events` with a title derived from
+    /// the app state, e.g. `"file.txt — Editor*"`. Nothing is sent to the
+    /// compositor when the title is unchanged. Does nothing for other than
+    /// window surfaces.
+    pub fn update_title(&mut self, title: &str) {
+        let Some(window) = self.kind.as_window() else {
+            return;
+        };
+        if self.title.as_deref() == Some(title) {
+            return;
+        }
+        trace!("[EGUI] Updating title of {} to {:?}", self.name, title);
+        window.set_title(title);
+        self.title = Some(title.to_string());
+    }
+
+    /// Window management actions the compositor supports, e.g. maximize
+    ///
+    /// Useful for hiding buttons of a client side title bar. All actions are
+    /// assumed available until the compositor advertises otherwise, and for
+    /// other than window surfaces.
+    pub fn capabilities(&self) -> WindowManagerCapabilities {
+        self.wm_capabilities
+    }
+
+    /// Mark the surface as urgent, e.g. when a background task finishes
+    ///
+    /// See `Application::request_attention`, support varies by compositor.
+    /// The hint can't be withdrawn, compositors clear it when the surface
+    /// gets focused, so `false` does nothing. Warns when unsupported.
+    pub fn set_urgent(&self, app: &Application, urgent: bool) {
+        if !urgent {
+            return;
+        }
+        if !app.request_attention(self.wl_surface()) {
+            log::warn!(
+                "[EGUI] Urgency hint for {} not supported, xdg-activation is not available",
+                self.name
+            );
+        }
+    }
+
+    /// Whether the compositor has maximized the window
+    pub fn is_maximized(&self) -> bool {
+        self.window_state.contains(WindowState::MAXIMIZED)
+    }
+
+    /// Apply an action of a `TitleBar`
+    ///
+    /// Moving uses `Application::last_pointer_serial` of the press that
+    /// started the drag. `Close` does nothing, the app decides how to close.
+    /// Does nothing for other than window surfaces.
+    pub fn apply_title_bar_action(&self, app: &Application, action: TitleBarAction) {
+        let Some(window) = self.kind.as_window() else {
+            return;
+        };
+        match action {
+            TitleBarAction::Move => {
+                if let Some(serial) = app.last_pointer_serial() {
+                    self.start_interactive_move(app, serial);
+                }
+            }
+            TitleBarAction::Minimize => window.set_minimized(),
+            TitleBarAction::ToggleMaximize if self.is_maximized() => window.unset_maximized(),
+            TitleBarAction::ToggleMaximize => window.set_maximized(),
+            TitleBarAction::Close => {}
+        }
+    }
+
+    /// Set the parent window, e.g. for dialogs
+    ///
+    /// The compositor usually stacks the window above its parent and may
+    /// center it on the parent. Does nothing for other than window surfaces.
+    pub fn set_parent<P: Into<Kind> + Clone>(&self, parent: Option<&EguiSurfaceState<P>>) {
+        let Some(window) = self.kind.as_window() else {
+            return;
+        };
+        window.set_parent(parent.and_then(|parent| parent.kind.as_window()));
+    }
+
+    /// Make the window modal to its parent, see `set_parent`
+    ///
+    /// Needs `xdg_wm_dialog_v1`, without it the window is only parented. Does
+    /// nothing for other than window surfaces.
+    pub fn set_modal(&mut self, app: &Application, modal: bool) {
+        let Some(window) = self.kind.as_window() else {
+            return;
+        };
+        if self.dialog.is_none() {
+            let Some(manager) = app
+                .dialog_manager
+                .as_ref()
+                .and_then(|manager| manager.get().ok())
+            else {
+                trace!("[EGUI] xdg_wm_dialog_v1 not available for {}", self.name);
+                return;
+            };
+            self.dialog = Some(manager.get_xdg_dialog(window.xdg_toplevel(), &app.qh, ()));
+        }
+        if let Some(dialog) = &self.dialog {
+            if modal {
+                dialog.set_modal();
+            } else {
+                dialog.unset_modal();
+            }
+        }
+    }
+
+    /// Keep the screen awake while the surface is visible, e.g. during video
+    /// playback
+    ///
+    /// Needs `zwp_idle_inhibit_manager_v1`, without it this warns and does
+    /// nothing. The inhibitor is destroyed with the surface state.
+    pub fn inhibit_idle(&mut self, app: &Application, inhibit: bool) {
+        if !inhibit {
+            if let Some(inhibitor) = self.idle_inhibitor.take() {
+                trace!("[EGUI] Idle inhibit disabled for {}", self.name);
+                inhibitor.destroy();
+            }
+            return;
+        }
+        if self.idle_inhibitor.is_some() {
+            return;
+        }
+        let Some(manager) = app
+            .idle_inhibit_manager
+            .as_ref()
+            .and_then(|manager| manager.get().ok())
+        else {
+            log::warn!(
+                "[EGUI] zwp_idle_inhibit_manager_v1 not available for {}",
+                self.name
+            );
+            return;
+        };
+        trace!("[EGUI] Idle inhibit enabled for {}", self.name);
+        self.idle_inhibitor = Some(manager.create_inhibitor(self.wl_surface(), &app.qh, ()));
+    }
+
+    /// Open a popup, e.g. a context menu, with its top left corner at `pos`
+    ///
+    /// `pos` is in EGUI points of this surface, e.g.
+    /// `response.interact_pointer_pos()` of a right click. The compositor
+    /// flips or slides the popup to keep it on screen. The popup grabs the
+    /// pointer and keyboard when there was a recent button press, so clicking
+    /// outside dismisses it; keep it in an `Option`, whose `handle_events`
+    /// drops it once dismissed. Returns `None` for other than window surfaces,
+    /// or if the popup or its renderer can't be created.
+    pub fn open_popup_at(
+        &self,
+        app: &Application,
+        pos: egui::Pos2,
+        size: LogicalSize,
+    ) -> Option<EguiSurfaceState<Popup>> {
+        self.open_popup_with_options(app, pos, size, PopupOptions::default())
+    }
+
+    /// Open a popup at `pos` with the given placement, see `open_popup_at`
+    ///
+    /// E.g. a submenu is anchored to the right edge of its item, with only
+    /// `FlipX` to open it on the left side near the right edge of the screen.
+    pub fn open_popup_with_options(
+        &self,
+        app: &Application,
+        pos: egui::Pos2,
+        size: LogicalSize,
+        options: PopupOptions,
+    ) -> Option<EguiSurfaceState<Popup>> {
+        let window = self.kind.as_window()?;
+
+        // EGUI points differ from surface coordinates with a fixed resolution
+        let layout = self.layout_size();
+        let x = pos.x * self.size.width as f32 / layout.width.max(1) as f32;
+        let y = pos.y * self.size.height as f32 / layout.height.max(1) as f32;
+
+        let size = size.at_least_one();
+        let positioner = XdgPositioner::new(&app.xdg_shell).ok()?;
+        positioner.set_size(size.width as i32, size.height as i32);
+        positioner.set_anchor_rect(x as i32, y as i32, 1, 1);
+        positioner.set_anchor(options.anchor);
+        positioner.set_gravity(options.gravity);
+        positioner.set_constraint_adjustment(options.constraint_adjustment);
+        positioner.set_offset(options.offset.0, options.offset.1);
+        let popup = Popup::new(
+            window.xdg_surface(),
+            &positioner,
+            &app.qh,
+            &app.compositor_state,
+            &app.xdg_shell,
+        )
+        .ok()?;
+
+        // Grab must be requested before the popup is mapped
+        if let (Some(seat), Some(serial)) = (app.pointer_seat(), app.last_pointer_serial()) {
+            popup.xdg_popup().grab(seat, serial);
+        }
+        // Initial commit without a buffer, the compositor answers with a
+        // configure
+        popup.wl_surface().commit();
+        trace!("[EGUI] Opening popup of {} at {:?}", self.name, (x, y));
+
+        EguiSurfaceState::try_new(app, popup, size.width, size.height)
+            .inspect_err(|err| log::warn!("[EGUI] Popup of {} not opened: {err}", self.name))
+            .ok()
+    }
+
+    /// Set the color of the area EGUI doesn't paint, transparent by default
+    ///
+    /// E.g. a bar whose panel doesn't cover the whole surface. Turns off
+    /// `set_clear_with_window_fill`.
+    pub fn set_clear_color(&mut self, color: egui::Color32) {
+        self.clear_with_window_fill = false;
+        self.renderer.set_clear_color(color);
+        self.request_frame();
+    }
+
+    /// Clear with the `window_fill` of the current EGUI visuals
+    ///
+    /// Keeps the uncovered area consistent with the theme, also when the
+    /// visuals change at runtime.
+    pub fn set_clear_with_window_fill(&mut self, enabled: bool) {
+        self.clear_with_window_fill = enabled;
+        if !enabled {
+            self.renderer.set_clear_color(egui::Color32::TRANSPARENT);
+        }
+        self.request_frame();
+    }
+
+    /// Set the window geometry, the visible part of the window in logical
+    /// pixels, e.g. excluding client side shadows
+    ///
+    /// The compositor uses it for snapping and tiling, and the configured size
+    /// is the size of this rectangle. `None` uses the whole surface, which is
+    /// the default. Applied with the next rendered frame. Does nothing for
+    /// other than window surfaces.
+    pub fn set_window_geometry(&mut self, geometry: Option<(i32, i32, i32, i32)>) {
+        self.window_geometry = geometry;
+        self.apply_window_geometry();
+        self.request_frame();
+    }
+
+    fn apply_window_geometry(&self) {
+        let Some(window) = self.kind.as_window() else {
+            return;
+        };
+        let whole = (0, 0, self.size.width as i32, self.size.height as i32);
+        let (x, y, width, height) = self.window_geometry.unwrap_or(whole);
+        // Zero size is a protocol error
+        window
+            .xdg_surface()
+            .set_window_geometry(x, y, width.max(1), height.max(1));
+    }
+
+    /// Start an interactive move of the window, e.g. when dragging a custom
+    /// title bar
+    ///
+    /// `serial` must be from a pointer button press that is still held, see
+    /// `Application::last_pointer_serial`. Does nothing for other than window
+    /// surfaces.
+    pub fn start_interactive_move(&self, app: &Application, serial: u32) {
+        let (Some(window), Some(seat)) = (self.kind.as_window(), app.pointer_seat()) else {
+            trace!("[EGUI] Interactive move not available for {}", self.name);
+            return;
+        };
+        window.xdg_toplevel()._move(seat, serial);
     }
 
-    /// Request a frame via dispatching
+    /// Start an interactive resize of the window from the given edge
     ///
-    /// Strictly this wouldn't be necessary, as
-    /// `egui_frame_scheduler.schedule_frame` could be used. However, this
-    /// method can be used to break the FPS limit, and I wanted to break it
-    /// for the window resizing events at least for now.
+    /// `serial` must be from a pointer button press that is still held, see
+    /// `Application::last_pointer_serial`. Does nothing for other than window
+    /// surfaces.
+    pub fn start_interactive_resize(&self, app: &Application, serial: u32, edge: ResizeEdge) {
+        let (Some(window), Some(seat)) = (self.kind.as_window(), app.pointer_seat()) else {
+            trace!("[EGUI] Interactive resize not available for {}", self.name);
+            return;
+        };
+        window.xdg_toplevel().resize(seat, serial, edge);
+    }
+
+    fn fractional_scale_changed(&mut self, new_scale: f64) {
+        if new_scale <= 0.0 {
+            return;
+        }
+        self.preferred_scale = Some(Scale::new(new_scale as f32));
+        self.update_layout_size();
+    }
+
+    /// Request a frame callback from the compositor
+    ///
+    /// This commits the surface without new content, so it must not be used
+    /// when pending state (e.g. size) should be applied together with the
+    /// next buffer.
     fn request_dispatch_frame(&mut self, app: &mut Application) {
         self.wl_surface().frame(&app.qh, self.wl_surface().clone());
         self.wl_surface().commit();
-        app.conn.flush().unwrap();
+        app.flush();
+    }
+
+    /// Render and present a single frame right away
+    ///
+    /// For surfaces that change on demand, e.g. a wallpaper switching images.
+    /// The frame is committed without requesting a frame callback, and frame
+    /// throttling and pausing don't apply, so the compositor isn't kept in an
+    /// animation loop. Animated content should go through `handle_events`
+    /// and `request_frame` instead, which follow the compositor's pace.
+    pub fn render_once(&mut self, app: &mut Application, ui: &mut impl FnMut(&mut egui::Ui)) {
+        self.render(ui);
+        app.flush();
     }
 
     /// Request a frame via Frame scheduler
@@ -210,13 +1314,151 @@ impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
         self.frame_scheduler.schedule_frame(Duration::ZERO);
     }
 
+    /// Request a frame after `delay`, an earlier scheduled frame is kept
+    pub fn request_frame_after(&mut self, delay: Duration) {
+        self.frame_scheduler.schedule_frame(delay);
+    }
+
+    /// When the next frame requested by EGUI (e.g. `request_repaint_after`)
+    /// is due, `None` if no frame is scheduled
+    pub fn next_frame_deadline(&self) -> Option<Instant> {
+        self.frame_scheduler.next_deadline()
+    }
+
+    /// Render only on the compositor's frame callbacks
+    ///
+    /// By default repaints requested by EGUI render as soon as the frame
+    /// scheduler fires. In this mode they only request a frame callback, and
+    /// the render happens when the compositor is ready to show a new frame, so
+    /// a hidden or throttled surface doesn't render in vain.
+    ///
+    /// The frame scheduler keeps waking the loop while the callback is
+    /// pending. If none arrives in `FRAME_CALLBACK_TIMEOUT`, the frame is
+    /// rendered anyway and animations continue at that pace, so the UI isn't
+    /// frozen when the compositor stops sending callbacks. No other callback
+    /// is requested while one is outstanding. Surfaces the
+    /// compositor marks suspended skip rendering regardless, and their
+    /// scheduler slows down, see `set_fps_target`.
+    pub fn set_render_on_frame_only(&mut self, enabled: bool) {
+        self.render_on_frame_only = enabled;
+        self.frame_callback_requested = None;
+    }
+
+    /// Whether a frame callback has been requested but not yet received, see
+    /// `set_render_on_frame_only`
+    pub fn is_waiting_for_frame_callback(&self) -> bool {
+        self.frame_callback_requested.is_some()
+    }
+
+    /// Number of frames rendered, the frame number in the log messages
+    pub fn frame_number(&self) -> u64 {
+        self.renders
+    }
+
+    /// Stop repainting while the surface doesn't have keyboard focus
+    ///
+    /// Saves power by pausing animations of unfocused surfaces. Input is still
+    /// processed, but only rendered on configure or when the focus returns.
+    /// EGUI sees the focus state either way, see `egui::InputState::focused`.
+    ///
+    /// Surfaces that can't get keyboard focus are never paused, e.g. layer
+    /// surfaces without keyboard interactivity, see `set_exclusive_keyboard`.
+    pub fn set_pause_when_unfocused(&mut self, pause: bool) {
+        self.pause_when_unfocused = pause;
+        if !pause {
+            self.request_frame();
+        }
+    }
+
+    /// Drive EGUI animations with the compositor's frame callback timestamps
+    ///
+    /// Keeps animations in step with the compositor's vsync instead of the
+    /// time the frame happened to be rendered, reducing microstutter. Works
+    /// best with `set_render_on_frame_only`, otherwise frames between
+    /// callbacks extrapolate from the latest callback. Off by default.
+    pub fn set_use_compositor_clock(&mut self, enabled: bool) {
+        self.input_state.set_use_compositor_clock(enabled);
+    }
+
+    /// Set the minimum time between renders, `None` to render on every frame
+    ///
+    /// Frames arriving sooner after the previous render are dropped and
+    /// rendered once the interval has passed, the input they carried is
+    /// processed by that render. Keeps e.g. dragging a slider from presenting
+    /// faster than the display refreshes. Unlike `set_fps_target` this limits
+    /// all renders, not just the repaints EGUI requests.
+    pub fn set_min_frame_interval(&mut self, interval: Option<Duration>) {
+        self.min_frame_interval = interval;
+    }
+
+    /// Time left until the minimum frame interval allows rendering again
+    fn frame_interval_remaining(&self) -> Option<Duration> {
+        let last_render = self.frame_timings.map(|(_, last_render)| last_render);
+        frame_interval_remaining(self.min_frame_interval, last_render, Instant::now())
+    }
+
+    /// Create a channel for delivering results from other threads to `ui()`
+    ///
+    /// Sending wakes the event loop and schedules a frame for this surface,
+    /// drain the receiver in `ui()`.
+    pub fn channel<V>(&self) -> (SurfaceSender<V>, Receiver<V>) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let sender = SurfaceSender {
+            sender,
+            schedule_frame: Arc::new(self.frame_scheduler.create_scheduler()),
+        };
+        (sender, receiver)
+    }
+
+    /// Set extra usages for the swapchain textures, see
+    /// `EguiWgpuRenderer::set_extra_surface_usages`
+    pub fn set_extra_surface_usages(&mut self, usages: wgpu::TextureUsages) {
+        self.renderer.set_extra_surface_usages(usages);
+    }
+
+    /// Texture usages the swapchain is configured with
+    pub fn surface_usages(&self) -> wgpu::TextureUsages {
+        self.renderer.surface_usages()
+    }
+
+    /// Set a callback receiving the GPU time of rendered frames, see
+    /// `EguiWgpuRenderer::set_gpu_timing_callback`
+    pub fn set_gpu_timing_callback(
+        &mut self,
+        callback: impl FnMut(Duration) + Send + 'static,
+    ) -> bool {
+        self.renderer.set_gpu_timing_callback(callback)
+    }
+
     /// Process EGUI frame (layout, input) without GPU rendering
     /// This is cheap and can be called frequently
     fn process_egui_frame(&mut self, ui: &mut impl FnMut(&mut egui::Ui)) {
-        let raw_input = self.input_state.take_raw_input();
+        #[cfg(feature = "accesskit")]
+        self.accesskit
+            .begin_frame(&self.egui_context, &mut self.input_state);
+        let mut raw_input = self.input_state.take_raw_input();
+        // EGUI expects the info of the viewport being run
+        let mut viewport_info = raw_input
+            .viewports
+            .drain()
+            .next()
+            .map(|(_, info)| info)
+            .unwrap_or_default();
+        if std::mem::take(&mut self.close_requested) {
+            viewport_info.events.push(egui::ViewportEvent::Close);
+        }
+        raw_input.viewport_id = self.viewport_id;
+        raw_input.viewports.insert(self.viewport_id, viewport_info);
         self.egui_context
-            .set_pixels_per_point(self.physical_scale() as f32);
-        let full_output = self.egui_context.run_ui(raw_input, ui);
+            .set_pixels_per_point(self.egui_scale().get());
+        let mut full_output = self.egui_context.run_ui(raw_input, ui);
+        self.viewports
+            .update(std::mem::take(&mut full_output.viewport_output));
+        self.viewport_commands
+            .extend(self.viewports.take_commands(self.viewport_id));
+        #[cfg(feature = "accesskit")]
+        self.accesskit
+            .update(full_output.platform_output.accesskit_update.take());
         for command in &full_output.platform_output.commands {
             self.input_state.handle_output_command(command);
         }
@@ -231,12 +1473,14 @@ impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
     /// Only call this when necessary (e.g., on frame callback or when content
     /// changed)
     fn render_to_wgpu(&mut self, full_output: egui::FullOutput) {
-        let width = self.width.saturating_mul(self.physical_scale());
-        let height = self.height.saturating_mul(self.physical_scale());
-        let pixels_per_point = self.physical_scale() as f32;
+        let size = self.physical_size();
+        let scale = self.egui_scale();
 
-        self.renderer
-            .render_to_wgpu(full_output, width, height, pixels_per_point);
+        if self.clear_with_window_fill {
+            let fill = self.egui_context.global_style().visuals.window_fill;
+            self.renderer.set_clear_color(fill);
+        }
+        self.renderer.render(full_output, size, scale);
 
         // Update frame timings
         let now = Instant::now();
@@ -249,26 +1493,31 @@ impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
     }
 
     /// Full render of EGUI frame (layout, input + GPU rendering)
+    ///
+    /// WGPU owns the commit: presenting attaches, damages and commits the
+    /// surface, so exactly one commit with content happens per frame and
+    /// nothing is committed after it.
     fn render(&mut self, ui: &mut impl FnMut(&mut egui::Ui)) {
         self.process_egui_frame(ui);
 
         if self.suspended {
             trace!(
-                "[EGUI] Skipping rendering for suspended surface {:?}",
-                self.wl_surface().id()
+                "[EGUI] Skipping rendering for suspended surface {}",
+                self.name
             );
             return;
         }
 
         if let Some(full_output) = self.last_fulloutput.take() {
+            self.renders += 1;
+            trace!(
+                "[EGUI] Rendering frame {} of surface {}",
+                self.renders, self.name
+            );
             self.render_to_wgpu(full_output);
         }
     }
 
-    fn physical_scale(&self) -> u32 {
-        self.scale_factor.max(1) as u32
-    }
-
     /// Send cursor position to Wayland
     fn sync_text_input_cursor(&mut self, app: &Application) {
         let new_ime = self
@@ -300,15 +1549,132 @@ impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
                         ti.commit();
                     }
                 }
-                None => {
-                    if let Some(ti) = &app.text_input {
-                        trace!("[IME] Deactivating Text Input");
-                        ti.disable();
-                        ti.commit();
-                    }
+                None => {
+                    if let Some(ti) = &app.text_input {
+                        trace!("[IME] Deactivating Text Input");
+                        ti.disable();
+                        ti.commit();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Apply visuals set with `Application::set_egui_visuals` if they changed
+    fn sync_visuals(&mut self, app: &Application) {
+        if self.visuals_generation == app.egui_visuals_generation() {
+            return;
+        }
+        self.visuals_generation = app.egui_visuals_generation();
+        if let Some(visuals) = app.egui_visuals() {
+            trace!(
+                "[EGUI] Applying application visuals to surface {}",
+                self.name
+            );
+            self.egui_context.set_visuals(visuals.clone());
+            self.request_frame();
+        }
+    }
+
+    /// Add fonts added with `Application::add_egui_font` since the last call
+    fn sync_fonts(&mut self, app: &Application) {
+        let fonts = app.egui_fonts();
+        if self.fonts_applied == fonts.len() {
+            return;
+        }
+        for font in &fonts[self.fonts_applied..] {
+            trace!("[EGUI] Adding font {} to surface {}", font.name, self.name);
+            self.egui_context.add_font(font.clone());
+        }
+        self.fonts_applied = fonts.len();
+        self.request_frame();
+    }
+
+    /// Upload and free textures of `Application::register_texture`
+    fn sync_textures(&mut self, app: &Application) {
+        if self.textures_generation == app.shared_textures_generation() {
+            return;
+        }
+        self.textures_generation = app.shared_textures_generation();
+        for (index, texture) in app.shared_textures().iter().enumerate() {
+            let key = SharedTexture(index).egui_id();
+            let loaded = self
+                .egui_context
+                .data(|data| data.get_temp::<egui::TextureHandle>(key).is_some());
+            match texture {
+                Some(texture) if !loaded => {
+                    trace!("[EGUI] Uploading texture {} to {}", texture.name, self.name);
+                    let handle = self.egui_context.load_texture(
+                        texture.name.clone(),
+                        texture.image.clone(),
+                        texture.options,
+                    );
+                    self.egui_context
+                        .data_mut(|data| data.insert_temp(key, handle));
+                }
+                // Dropping the handle frees the texture
+                None if loaded => {
+                    self.egui_context
+                        .data_mut(|data| data.remove::<egui::TextureHandle>(key));
                 }
+                _ => {}
             }
         }
+        self.request_frame();
+    }
+
+    /// Add a font to this surface, e.g. from `std::fs::read("font.ttf")`
+    ///
+    /// The font is used for glyphs the default fonts of `family` lack, so
+    /// non-Latin scripts such as CJK render instead of showing boxes. Use
+    /// `Application::add_egui_font` to add it to all surfaces.
+    pub fn add_font(&mut self, name: impl Into<String>, bytes: Vec<u8>, family: egui::FontFamily) {
+        self.egui_context
+            .add_font(crate::application::font_insert(name.into(), bytes, family));
+        self.request_frame();
+    }
+
+    /// Follow `Application::set_low_power`, capping the FPS target to
+    /// `LOW_POWER_FPS` while it's on
+    fn sync_power(&mut self, app: &Application) {
+        if self.low_power != app.is_low_power() {
+            self.low_power = app.is_low_power();
+            trace!("[EGUI] Low power mode of {}: {}", self.name, self.low_power);
+            self.apply_fps_target();
+        }
+    }
+
+    /// Repaint or reconfigure if requested with `Application::redraw_all` or
+    /// `Application::reconfigure_all`
+    fn sync_redraw(&mut self, app: &Application) {
+        if self.reconfigure_generation != app.reconfigure_generation() {
+            self.reconfigure_generation = app.reconfigure_generation();
+            trace!("[EGUI] Reconfiguring surface {}", self.name);
+            self.renderer.force_reconfigure();
+            self.request_frame();
+        }
+        if self.redraw_generation != app.redraw_generation() {
+            self.redraw_generation = app.redraw_generation();
+            trace!("[EGUI] Redrawing surface {}", self.name);
+            self.request_frame();
+        }
+    }
+
+    /// Position of the popup relative to the parent's window geometry, as
+    /// placed by the compositor, `None` before the first configure
+    ///
+    /// Differs from the requested position when the popup was flipped or slid
+    /// to stay on screen.
+    pub fn popup_position(&self) -> Option<(i32, i32)> {
+        self.popup_position
+    }
+
+    /// Whether the compositor dismissed the popup
+    ///
+    /// A dismissed popup is never shown again, drop the state to release the
+    /// surface.
+    pub fn is_closed(&self) -> bool {
+        self.closed
     }
 
     /// Get the last frame timings (previous frame time, current frame time)
@@ -338,47 +1704,127 @@ impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
         events: &[WaylandEvent],
         ui: &mut impl FnMut(&mut egui::Ui),
     ) {
-        for event in events {
-            if let Some(surface) = event.get_wl_surface() {
-                if surface.id() != self.wl_surface().id() {
-                    continue;
-                }
+        let id = self.wl_surface().id();
+        let own_events = events.iter().filter(move |event| {
+            event
+                .get_wl_surface()
+                .is_none_or(|surface| surface.id() == id)
+        });
+        self.handle_own_events(app, own_events, DispatchEvents::Flat(events), ui);
+    }
+
+    /// Like `handle_events`, for a dispatch already grouped by surface, so the
+    /// events aren't compared against this surface one by one
+    pub fn handle_grouped_events(
+        &mut self,
+        app: &mut Application,
+        events: &GroupedWaylandEvents,
+        ui: &mut impl FnMut(&mut egui::Ui),
+    ) {
+        let own_events = events.for_surface(self.wl_surface());
+        self.handle_own_events(app, own_events, DispatchEvents::Grouped(events), ui);
+    }
+
+    /// Events of this surface and of no particular surface, the whole
+    /// `dispatch` goes to the deferred viewports
+    fn handle_own_events<'a>(
+        &mut self,
+        app: &mut Application,
+        events: impl Iterator<Item = &'a WaylandEvent> + Clone,
+        dispatch: DispatchEvents,
+        ui: &mut impl FnMut(&mut egui::Ui),
+    ) {
+        let renders = self.renders;
+        self.sync_visuals(app);
+        self.sync_fonts(app);
+        self.sync_textures(app);
+        self.sync_redraw(app);
+        self.sync_power(app);
+        self.input_state
+            .set_max_buffered_events(app.max_buffered_events());
+
+        // Pointer events of a `wl_pointer.frame` (e.g. motion and axis) are
+        // atomic, they're processed together in one EGUI pass
+        let mut pointer_pending = false;
+
+        // SCTK acks each configure serial before the handler runs, so only the
+        // latest configure of a batch needs to be applied, e.g. during an
+        // interactive resize the compositor may send many at once
+        let last_configure = events
+            .clone()
+            .enumerate()
+            .filter(|(_, event)| event.is_configure())
+            .map(|(index, _)| index)
+            .last();
+
+        for (index, event) in events.enumerate() {
+            if event.is_configure() && Some(index) != last_configure {
+                trace!("[EGUI] Skipping superseded configure of {}", self.name);
+                continue;
+            }
+            if pointer_pending && !matches!(event, WaylandEvent::PointerEvent(_)) {
+                pointer_pending = false;
+                self.process_pointer_frame(app, ui);
             }
             match event {
                 WaylandEvent::WindowConfigure(_, configure) => {
-                    let width = configure
-                        .new_size
-                        .0
-                        .unwrap_or_else(|| NonZero::new(self.init_width).unwrap())
-                        .get();
-                    let height = configure
-                        .new_size
-                        .1
-                        .unwrap_or_else(|| NonZero::new(self.init_height).unwrap())
-                        .get();
-
-                    self.configure(app, width, height, Some(configure.state));
-                    self.request_dispatch_frame(app);
+                    let size = window_configure_size(configure.new_size, self.size);
+
+                    self.wm_capabilities = configure.capabilities;
+                    self.window_state = configure.state;
+                    self.configure(app, size, Some(configure.state));
+                    // Render right away, the commit of the new buffer also
+                    // applies the acked configure and viewport, committing
+                    // without content first would show the old buffer resized
+                    self.render(ui);
                 }
                 WaylandEvent::LayerShellConfigure(_, config) => {
-                    let width = config.new_size.0;
-                    let height = config.new_size.1;
+                    // Zero means the client decides the size, keep the current
+                    let size = LogicalSize::from(config.new_size).or_current(self.size);
 
-                    self.configure(app, width, height, None);
-                    self.request_dispatch_frame(app);
+                    self.configure(app, size, None);
+                    self.render(ui);
                 }
                 WaylandEvent::PopupConfigure(_, config) => {
-                    let width = config.width as u32;
-                    let height = config.height as u32;
+                    // The compositor may have flipped or slid the popup, and
+                    // shrunk it with `ConstraintAdjustment::ResizeX/Y`
+                    if self.popup_position != Some(config.position) {
+                        trace!("[EGUI] Popup {} placed at {:?}", self.name, config.position);
+                        self.popup_position = Some(config.position);
+                    }
+                    let size =
+                        LogicalSize::new(config.width.max(0) as u32, config.height.max(0) as u32)
+                            .or_current(self.size);
 
-                    self.configure(app, width, height, None);
-                    self.request_dispatch_frame(app);
-                }
-                WaylandEvent::Frame(_, _) => {
+                    self.configure(app, size, None);
                     self.render(ui);
                 }
+                WaylandEvent::PopupDone(_) => {
+                    // The popup is not shown again, free the WGPU surface right
+                    // away, the owner should drop this state, see `is_closed`
+                    trace!("[EGUI] Popup {} done", self.name);
+                    self.closed = true;
+                    self.suspended = true;
+                    self.renderer.suspend();
+                    self.frame_scheduler.cancel();
+                }
+                WaylandEvent::Frame(_, time) => {
+                    self.input_state.handle_frame_time(*time);
+                    self.handle_frame(app, ui, Some(*time));
+                }
+                WaylandEvent::ScheduledFrame(_) => self.handle_frame(app, ui, None),
                 WaylandEvent::ScaleFactorChanged(_, factor) => {
+                    let old_scale = self.scale();
                     self.scale_factor_changed(*factor);
+                    self.notify_scale_changed(old_scale);
+                    self.process_egui_frame(ui);
+                    self.sync_text_input_cursor(app);
+                    self.request_frame();
+                }
+                WaylandEvent::FractionalScaleChanged(_, scale) => {
+                    let old_scale = self.scale();
+                    self.fractional_scale_changed(*scale);
+                    self.notify_scale_changed(old_scale);
                     self.process_egui_frame(ui);
                     self.sync_text_input_cursor(app);
                     self.request_frame();
@@ -389,27 +1835,39 @@ impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
                         position: position.clone(),
                         kind: event_kind.clone(),
                     });
+                    pointer_pending = true;
+                }
+                WaylandEvent::PinchUpdate(_, scale_delta, _rotation) => {
+                    // Rotation isn't supported by EGUI, apps can read it from
+                    // the event directly
+                    self.input_state.handle_pinch_zoom(*scale_delta);
                     self.process_egui_frame(ui);
-                    if let Some(cursor) = self
-                        .last_fulloutput
-                        .as_ref()
-                        .map(|o| o.platform_output.cursor_icon)
-                    {
-                        app.set_cursor(egui_to_cursor_shape(cursor));
-                    }
-                    self.sync_text_input_cursor(app);
                 }
-                WaylandEvent::KeyboardEnter(_, _serials, _keysyms) => {
-                    self.handle_keyboard_enter();
+                WaylandEvent::KeyboardEnter(_, _serials, keysyms) => {
+                    self.handle_keyboard_enter(keysyms);
                     self.has_keyboard_focus = true;
+                    #[cfg(feature = "accesskit")]
+                    self.accesskit.set_focused(true);
+                    if let Some(on_keyboard_enter) = &mut self.on_keyboard_enter {
+                        on_keyboard_enter(&self.egui_context);
+                    }
                     self.process_egui_frame(ui);
                     self.sync_text_input_cursor(app);
+                    if self.pause_when_unfocused {
+                        self.request_frame();
+                    }
                 }
                 WaylandEvent::KeyboardLeave(_) => {
                     self.handle_keyboard_leave();
                     self.has_keyboard_focus = false;
+                    #[cfg(feature = "accesskit")]
+                    self.accesskit.set_focused(false);
                     self.process_egui_frame(ui);
                     self.sync_text_input_cursor(app);
+                    if self.pause_when_unfocused {
+                        // Show the unfocused appearance before pausing
+                        self.request_dispatch_frame(app);
+                    }
                 }
                 WaylandEvent::KeyPress(key_event) => {
                     if self.has_keyboard_focus {
@@ -449,13 +1907,13 @@ impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
                     // emitting events.
                     self.request_frame();
                 }
-                WaylandEvent::ImeEnter(surface) => {
-                    trace!("[IME] ImeEnter for surface {:?}", surface.id());
+                WaylandEvent::ImeEnter(_) => {
+                    trace!("[IME] ImeEnter for surface {}", self.name);
                     self.input_state.handle_ime_enter();
                     self.process_egui_frame(ui);
                 }
-                WaylandEvent::ImeLeave(surface) => {
-                    trace!("[IME] ImeLeave for surface {:?}", surface.id());
+                WaylandEvent::ImeLeave(_) => {
+                    trace!("[IME] ImeLeave for surface {}", self.name);
                     self.input_state.handle_ime_leave();
                     self.process_egui_frame(ui);
                 }
@@ -486,10 +1944,473 @@ impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
                 WaylandEvent::ImeDone(_serial) => {
                     // It doesn't seem to need this?
                 }
+                WaylandEvent::WindowRequestClose(window)
+                    if self.viewport_id != egui::ViewportId::ROOT
+                        && self.kind.as_window() == Some(window) =>
+                {
+                    // The `ui()` of the viewport sees `close_requested()`, and
+                    // its parent stops showing it in the next pass
+                    self.close_requested = true;
+                    self.process_egui_frame(ui);
+                    self.viewports
+                        .schedule_frame(egui::ViewportId::ROOT, Duration::ZERO);
+                }
+                _ => {}
+            }
+        }
+
+        if pointer_pending {
+            self.process_pointer_frame(app, ui);
+        }
+
+        if self.viewport_id == egui::ViewportId::ROOT {
+            self.handle_deferred_viewports(app, dispatch);
+        }
+        self.viewport_commands
+            .extend(self.viewports.take_commands(self.viewport_id));
+        self.apply_viewport_commands(app);
+        app.record_dispatch_handled((self.renders - renders) as usize);
+    }
+
+    /// Render a frame, `callback_time` is the timestamp of a frame callback or
+    /// `None` for frames of the frame scheduler
+    fn handle_frame(
+        &mut self,
+        app: &mut Application,
+        ui: &mut impl FnMut(&mut egui::Ui),
+        callback_time: Option<u32>,
+    ) {
+        let scheduled = callback_time.is_none();
+        if !scheduled {
+            // The outstanding callback has arrived
+            self.frame_callback_requested = None;
+        }
+        let can_focus = can_take_keyboard_focus(
+            self.kind.as_layer_surface().is_some(),
+            self.keyboard_interactivity,
+        );
+        if self.pause_when_unfocused && !self.has_keyboard_focus && can_focus && scheduled {
+            trace!("[EGUI] Paused unfocused surface {}", self.name);
+        } else if self.render_on_frame_only && scheduled {
+            let now = Instant::now();
+            match frame_callback_step(self.frame_callback_requested, now) {
+                FrameCallbackStep::Request => {
+                    trace!("[EGUI] Waiting frame callback for surface {}", self.name);
+                    self.frame_callback_requested = Some(now);
+                    self.request_dispatch_frame(app);
+                    // Wakes the loop if the callback never comes
+                    self.frame_scheduler.schedule_frame(FRAME_CALLBACK_TIMEOUT);
+                }
+                FrameCallbackStep::Wait => {}
+                FrameCallbackStep::Timeout => {
+                    // The callback stays outstanding, requesting another one
+                    // would pile them up on a surface the compositor doesn't
+                    // draw. The timeout starts over for the next render.
+                    trace!(
+                        "[EGUI] No frame callback for surface {}, rendering anyway",
+                        self.name
+                    );
+                    self.frame_callback_requested = Some(now);
+                    self.frame_scheduler.schedule_frame(FRAME_CALLBACK_TIMEOUT);
+                    self.render(ui);
+                }
+            }
+        } else if let Some(remaining) = self.frame_interval_remaining() {
+            trace!("[EGUI] Throttled surface {} for {:?}", self.name, remaining);
+            self.frame_scheduler.schedule_frame(remaining);
+        } else {
+            trace!(
+                "[EGUI] Frame of surface {} at time {:?}",
+                self.name, callback_time
+            );
+            self.render(ui);
+        }
+    }
+
+    /// Open windows for the deferred viewports shown in the last pass, close
+    /// the ones no longer shown, and run the open ones
+    fn handle_deferred_viewports(&mut self, app: &mut Application, dispatch: DispatchEvents) {
+        let shown: Vec<_> = self
+            .viewports
+            .outputs
+            .borrow()
+            .iter()
+            .filter_map(|(id, output)| {
+                let ui = output.viewport_ui_cb.clone()?;
+                Some((*id, ui, output.builder.clone()))
+            })
+            .collect();
+        self.deferred_viewports.retain(|id, viewport| {
+            let open = shown.iter().any(|(shown_id, ..)| shown_id == id);
+            if !open {
+                trace!("[EGUI] Closing viewport {}", viewport.surface.name);
+            }
+            open
+        });
+        for (id, ui, builder) in shown {
+            if let Some(viewport) = self.deferred_viewports.get_mut(&id) {
+                viewport.ui = ui;
+            } else if let Some(surface) = self.open_deferred_viewport(app, id, &builder) {
+                self.deferred_viewports
+                    .insert(id, DeferredViewport { surface, ui });
+            }
+        }
+        for viewport in self.deferred_viewports.values_mut() {
+            let viewport_ui = viewport.ui.clone();
+            let ui = &mut |ui: &mut egui::Ui| viewport_ui(ui);
+            match dispatch {
+                DispatchEvents::Flat(events) => viewport.surface.handle_events(app, events, ui),
+                DispatchEvents::Grouped(events) => {
+                    viewport.surface.handle_grouped_events(app, events, ui)
+                }
+            }
+        }
+    }
+
+    /// Open a window for a deferred viewport, sharing the device and textures
+    /// of this surface's renderer
+    ///
+    /// If the renderer can't draw to the window, the context falls back to
+    /// embedding its viewports.
+    fn open_deferred_viewport(
+        &self,
+        app: &Application,
+        id: egui::ViewportId,
+        builder: &egui::ViewportBuilder,
+    ) -> Option<EguiSurfaceState<Window>> {
+        let SurfaceRenderer::Gpu(renderer) = &self.renderer else {
+            return None;
+        };
+        let wl_surface = app.compositor_state.create_surface(&app.qh);
+        let renderer = match renderer.try_for_surface(&wl_surface) {
+            Ok(renderer) => renderer,
+            Err(err) => {
+                log::warn!("[EGUI] {}, embedding the viewports of {}", err, self.name);
+                wl_surface.destroy();
+                self.egui_context.set_embed_viewports(true);
+                return None;
+            }
+        };
+        let window =
+            app.xdg_shell
+                .create_window(wl_surface, WindowDecorations::ServerDefault, &app.qh);
+        if let Some(title) = &builder.title {
+            window.set_title(title.clone());
+        }
+        if let Some(app_id) = &builder.app_id {
+            window.set_app_id(app_id.clone());
+        }
+        if let Some(size) = builder.min_inner_size {
+            let size = self.points_to_logical(size);
+            window.set_min_size(Some((size.width, size.height)));
+        }
+        if let Some(size) = builder.max_inner_size {
+            let size = self.points_to_logical(size);
+            window.set_max_size(Some((size.width, size.height)));
+        }
+        window.commit();
+        let size = builder.inner_size.map_or(
+            LogicalSize::new(DEFAULT_VIEWPORT_SIZE.0, DEFAULT_VIEWPORT_SIZE.1),
+            |size| self.points_to_logical(size),
+        );
+        let mut surface = EguiSurfaceState::with_viewport(
+            app,
+            window,
+            size.width,
+            size.height,
+            self.egui_context.clone(),
+            SurfaceRenderer::Gpu(renderer),
+            id,
+            self.viewports.clone(),
+        );
+        trace!("[EGUI] Opened viewport {} of {}", surface.name, self.name);
+        // Visuals, fonts and textures of the app are already in the context
+        surface.visuals_generation = self.visuals_generation;
+        surface.fonts_applied = self.fonts_applied;
+        surface.textures_generation = self.textures_generation;
+        surface.set_zoom(self.zoom);
+        Some(surface)
+    }
+
+    /// Apply the `ViewportCommand`s sent from `ui()`, e.g.
+    /// `ctx.send_viewport_cmd(ViewportCommand::Title(..))`
+    ///
+    /// Commands without a Wayland counterpart, such as positioning the window,
+    /// are ignored. `Close` emits `WindowRequestClose` like the compositor's
+    /// close button does.
+    fn apply_viewport_commands(&mut self, app: &mut Application) {
+        use egui::ViewportCommand;
+        for command in std::mem::take(&mut self.viewport_commands) {
+            trace!("[EGUI] Viewport command for {}: {:?}", self.name, command);
+            if let ViewportCommand::InnerSize(size) = command {
+                self.request_inner_size(app, size);
+                continue;
+            }
+            let Some(window) = self.kind.as_window().cloned() else {
+                continue;
+            };
+            match command {
+                ViewportCommand::Title(title) => self.update_title(&title),
+                ViewportCommand::Close => app
+                    .get_event_emitter()
+                    .emit_events(vec![WaylandEvent::WindowRequestClose(window)]),
+                ViewportCommand::Minimized(true) => window.set_minimized(),
+                ViewportCommand::Maximized(true) => window.set_maximized(),
+                ViewportCommand::Maximized(false) => window.unset_maximized(),
+                ViewportCommand::Fullscreen(true) => window.set_fullscreen(None),
+                ViewportCommand::Fullscreen(false) => window.unset_fullscreen(),
+                ViewportCommand::MinInnerSize(size) => {
+                    let size = self.points_to_logical(size);
+                    window.set_min_size(Some((size.width, size.height)));
+                }
+                ViewportCommand::MaxInnerSize(size) => {
+                    let size = self.points_to_logical(size);
+                    window.set_max_size(Some((size.width, size.height)));
+                }
+                ViewportCommand::StartDrag => {
+                    self.apply_title_bar_action(app, TitleBarAction::Move);
+                }
+                ViewportCommand::RequestUserAttention(attention) => {
+                    self.set_urgent(app, attention != egui::UserAttentionType::Reset)
+                }
                 _ => {}
             }
         }
     }
+
+    /// Resize from `ViewportCommand::InnerSize`, windows only change size
+    /// when they're not maximized or fullscreen
+    fn request_inner_size(&mut self, app: &Application, size: egui::Vec2) {
+        let size = self.points_to_logical(size);
+        if self.kind.as_layer_surface().is_some() {
+            self.set_layer_size(size.width, size.height);
+        } else if self.kind.as_window().is_some() {
+            let fixed = WindowState::MAXIMIZED | WindowState::FULLSCREEN;
+            if self.window_state.intersects(fixed) {
+                return;
+            }
+            self.configure(app, size, Some(self.window_state));
+            self.request_frame();
+        }
+    }
+
+    /// EGUI points to logical pixels
+    fn points_to_logical(&self, size: egui::Vec2) -> LogicalSize {
+        let size = size * self.zoom;
+        LogicalSize::new(size.x.round() as u32, size.y.round() as u32)
+    }
+
+    /// Run EGUI once for the buffered pointer events, and update the cursor
+    /// if the pointer is over this surface
+    fn process_pointer_frame(&mut self, app: &mut Application, ui: &mut impl FnMut(&mut egui::Ui)) {
+        self.process_egui_frame(ui);
+        let cursor = pointer_cursor(
+            app.pointer_state().surface.as_ref(),
+            self.wl_surface(),
+            self.last_fulloutput
+                .as_ref()
+                .map(|o| o.platform_output.cursor_icon),
+        );
+        if let Some(cursor) = cursor {
+            app.set_cursor(cursor);
+        }
+        self.sync_text_input_cursor(app);
+    }
+}
+
+/// Size of a window after a configure
+///
+/// None means the client decides the size, e.g. on state only changes like
+/// activation, so the `current` size is kept (the one given to `new` before
+/// the first configure).
+fn window_configure_size(
+    new_size: (Option<NonZero<u32>>, Option<NonZero<u32>>),
+    current: LogicalSize,
+) -> LogicalSize {
+    LogicalSize::new(
+        new_size.0.map_or(0, NonZero::get),
+        new_size.1.map_or(0, NonZero::get),
+    )
+    .or_current(current)
+}
+
+/// Cursor of `surface` after a pointer frame, `None` when the pointer is
+/// over another surface
+///
+/// After a leave, the cursor belongs to the surface the pointer entered, which
+/// sets its own.
+fn pointer_cursor(
+    pointer_surface: Option<&WlSurface>,
+    surface: &WlSurface,
+    icon: Option<egui::CursorIcon>,
+) -> Option<Shape> {
+    if pointer_surface.is_some_and(|pointer_surface| pointer_surface.id() == surface.id()) {
+        icon.map(egui_to_cursor_shape)
+    } else {
+        None
+    }
+}
+
+/// Step of a scheduled frame with `set_render_on_frame_only`
+#[derive(Debug, PartialEq)]
+enum FrameCallbackStep {
+    /// No callback is outstanding, request one
+    Request,
+    /// Wait for the outstanding callback
+    Wait,
+    /// The callback hasn't come in `FRAME_CALLBACK_TIMEOUT`, render without it
+    Timeout,
+}
+
+/// Step at `now` for a callback outstanding since `requested`
+fn frame_callback_step(requested: Option<Instant>, now: Instant) -> FrameCallbackStep {
+    match requested {
+        None => FrameCallbackStep::Request,
+        Some(requested) if now.saturating_duration_since(requested) < FRAME_CALLBACK_TIMEOUT => {
+            FrameCallbackStep::Wait
+        }
+        Some(_) => FrameCallbackStep::Timeout,
+    }
+}
+
+/// Time left at `now` until `interval` has passed since `last_render`, `None`
+/// when a render is allowed
+fn frame_interval_remaining(
+    interval: Option<Duration>,
+    last_render: Option<Instant>,
+    now: Instant,
+) -> Option<Duration> {
+    interval?
+        .checked_sub(now.saturating_duration_since(last_render?))
+        .filter(|remaining| !remaining.is_zero())
+}
+
+/// Layer surface size for `anchor` and exclusive `zone`
+///
+/// Only an axis anchored to both opposite edges is left to the compositor
+/// with 0, other axes are at least 1 and the axis of the exclusive zone is at
+/// most the zone.
+fn layer_size(anchor: Anchor, zone: i32, width: u32, height: u32) -> (u32, u32) {
+    let horizontal = anchor & (Anchor::LEFT | Anchor::RIGHT);
+    let vertical = anchor & (Anchor::TOP | Anchor::BOTTOM);
+    let stretch_width = horizontal == Anchor::LEFT | Anchor::RIGHT;
+    let stretch_height = vertical == Anchor::TOP | Anchor::BOTTOM;
+
+    let mut size = (width.max(1), height.max(1));
+
+    // Exclusive zone applies to the single anchored edge, optionally
+    // stretched along it
+    if zone > 0 {
+        let zone = zone as u32;
+        if !vertical.is_empty() && !stretch_height && (horizontal.is_empty() || stretch_width) {
+            size.1 = size.1.min(zone);
+        } else if !horizontal.is_empty()
+            && !stretch_width
+            && (vertical.is_empty() || stretch_height)
+        {
+            size.0 = size.0.min(zone);
+        }
+    }
+
+    if stretch_width {
+        size.0 = 0;
+    }
+    if stretch_height {
+        size.1 = 0;
+    }
+    size
+}
+
+/// Whether the compositor can give a surface keyboard focus, layer surfaces
+/// only get it with keyboard interactivity
+fn can_take_keyboard_focus(
+    is_layer_surface: bool,
+    interactivity: Option<KeyboardInteractivity>,
+) -> bool {
+    !is_layer_surface
+        || matches!(
+            interactivity,
+            Some(KeyboardInteractivity::OnDemand | KeyboardInteractivity::Exclusive)
+        )
+}
+
+/// Sending half of `EguiSurfaceState::channel`
+pub struct SurfaceSender<V> {
+    sender: Sender<V>,
+    schedule_frame: Arc<dyn Fn(Duration) + Send + Sync>,
+}
+
+impl<V> SurfaceSender<V> {
+    /// Send a value and schedule a frame for the surface
+    ///
+    /// Fails if the receiver is dropped.
+    pub fn send(&self, value: V) -> Result<(), SendError<V>> {
+        self.sender.send(value)?;
+        (self.schedule_frame)(Duration::ZERO);
+        Ok(())
+    }
+}
+
+impl<V> Clone for SurfaceSender<V> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            schedule_frame: self.schedule_frame.clone(),
+        }
+    }
+}
+
+impl EguiSurfaceState<WlSurface> {
+    /// Render EGUI to a surface created elsewhere, e.g. a subsurface managed
+    /// by another library
+    ///
+    /// The caller owns the surface lifecycle: it sets up the role, and
+    /// destroys the surface after dropping this state. There are no configure
+    /// events for a plain surface, so the size is given here and changed with
+    /// `resize`. Input is routed by the surface id as for other surfaces.
+    pub fn from_raw_surface(
+        app: &Application,
+        wl_surface: &WlSurface,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let mut state = Self::new(app, wl_surface.clone(), width, height);
+        state.configure(app, LogicalSize::new(width, height), None);
+        state.request_frame();
+        state
+    }
+
+    /// Change the logical size of a surface created with `from_raw_surface`
+    pub fn resize(&mut self, app: &Application, size: LogicalSize) {
+        self.configure(app, size, None);
+        self.request_frame();
+    }
+}
+
+impl<T: Into<Kind> + Clone> Drop for EguiSurfaceState<T> {
+    fn drop(&mut self) {
+        // WGPU surface and the objects extending the Wayland surface must be
+        // destroyed before it, the Wayland surface goes when `t` is dropped
+        // after this
+        self.renderer.suspend();
+        self.viewports
+            .schedulers
+            .lock()
+            .unwrap()
+            .remove(&self.viewport_id);
+        if let Some(viewport) = self.viewport.take() {
+            viewport.destroy();
+        }
+        if let Some(fractional_scale) = self.fractional_scale.take() {
+            fractional_scale.destroy();
+        }
+        if let Some(inhibitor) = self.idle_inhibitor.take() {
+            inhibitor.destroy();
+        }
+        if let Some(dialog) = self.dialog.take() {
+            dialog.destroy();
+        }
+    }
 }
 
 impl<T: Into<Kind> + Clone> Deref for EguiSurfaceState<T> {
@@ -531,6 +2452,347 @@ impl<T: Into<Kind> + Clone> OptionEguiSurfaceStateExt<T> for Option<EguiSurfaceS
     ) -> () {
         if let Some(surface_state) = self {
             surface_state.handle_events(app, events, ui);
+            // Dismissed popups are never shown again
+            if matches!(surface_state.kind, Kind::Popup(_)) && surface_state.is_closed() {
+                *self = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unconfigured_window(app: &Application) -> Window {
+        app.xdg_shell.create_window(
+            app.compositor_state.create_surface(&app.qh),
+            WindowDecorations::ServerDefault,
+            &app.qh,
+        )
+    }
+
+    /// Window and its first configure
+    fn configured_window(app: &mut Application) -> (EguiSurfaceState<Window>, WaylandEvent) {
+        let window = unconfigured_window(app);
+        window.commit();
+        let surface = EguiSurfaceState::new_with_software_fallback(app, window, 200, 100);
+        app.roundtrip().expect("Roundtrip failed");
+        let configure = app
+            .take_wayland_events()
+            .into_iter()
+            .find(|event| {
+                event.is_configure()
+                    && event
+                        .get_wl_surface()
+                        .is_some_and(|wl_surface| *wl_surface == *surface.wl_surface())
+            })
+            .expect("No configure");
+        (surface, configure)
+    }
+
+    fn pointer_event(surface: &EguiSurfaceState<Window>, kind: PointerEventKind) -> WaylandEvent {
+        WaylandEvent::PointerEvent((surface.wl_surface().clone(), (20.0, 20.0), kind))
+    }
+
+    fn hover_pos(surface: &EguiSurfaceState<Window>) -> Option<egui::Pos2> {
+        surface.egui_context.input(|i| i.pointer.hover_pos())
+    }
+
+    #[test]
+    fn state_only_configure_keeps_the_size() {
+        let current = LogicalSize::new(800, 600);
+        assert_eq!(window_configure_size((None, None), current), current);
+        assert_eq!(
+            window_configure_size((NonZero::new(1024), None), current),
+            LogicalSize::new(1024, 600)
+        );
+        assert_eq!(
+            window_configure_size((NonZero::new(640), NonZero::new(480)), current),
+            LogicalSize::new(640, 480)
+        );
+    }
+
+    #[test]
+    fn only_the_surface_under_the_pointer_sets_the_cursor() {
+        let [a, b] = crate::application::tests::surfaces();
+        let link = Some(egui::CursorIcon::PointingHand);
+        assert_eq!(pointer_cursor(Some(&a), &a, link), Some(Shape::Pointer));
+        // Leave of A and enter of B in one pointer frame
+        assert_eq!(pointer_cursor(Some(&b), &a, link), None);
+        assert_eq!(
+            pointer_cursor(Some(&b), &b, Some(egui::CursorIcon::Default)),
+            Some(Shape::Default)
+        );
+        // Left all surfaces
+        assert_eq!(pointer_cursor(None, &a, link), None);
+        assert_eq!(pointer_cursor(Some(&a), &a, None), None);
+    }
+
+    #[test]
+    fn frame_callback_times_out_without_another_request() {
+        let start = Instant::now();
+        assert_eq!(frame_callback_step(None, start), FrameCallbackStep::Request);
+        let requested = Some(start);
+        assert_eq!(
+            frame_callback_step(requested, start),
+            FrameCallbackStep::Wait
+        );
+        let almost = start + FRAME_CALLBACK_TIMEOUT - Duration::from_millis(1);
+        assert_eq!(
+            frame_callback_step(requested, almost),
+            FrameCallbackStep::Wait
+        );
+        let late = start + FRAME_CALLBACK_TIMEOUT;
+        assert_eq!(
+            frame_callback_step(requested, late),
+            FrameCallbackStep::Timeout
+        );
+
+        // The fallback render restarts the timeout of the outstanding callback
+        let requested = Some(late);
+        assert_eq!(
+            frame_callback_step(requested, late + Duration::from_millis(16)),
+            FrameCallbackStep::Wait
+        );
+        assert_eq!(
+            frame_callback_step(requested, late + FRAME_CALLBACK_TIMEOUT),
+            FrameCallbackStep::Timeout
+        );
+    }
+
+    #[test]
+    fn min_frame_interval_limits_the_renders() {
+        let start = Instant::now();
+        let interval = Some(Duration::from_millis(16));
+        let mut last_render = None;
+        let mut renders = 0;
+        // E.g. dragging a slider sends motion every 4 ms for 100 ms
+        for ms in (0..100).step_by(4) {
+            let now = start + Duration::from_millis(ms);
+            if frame_interval_remaining(interval, last_render, now).is_none() {
+                last_render = Some(now);
+                renders += 1;
+            }
+        }
+        // At 0, 16, 32, 48, 64, 80 and 96 ms
+        assert_eq!(renders, 7);
+
+        let now = start + Duration::from_millis(100);
+        assert_eq!(
+            frame_interval_remaining(interval, last_render, now),
+            Some(Duration::from_millis(12))
+        );
+        assert_eq!(frame_interval_remaining(None, last_render, now), None);
+        // Nothing rendered yet
+        assert_eq!(frame_interval_remaining(interval, None, now), None);
+    }
+
+    #[test]
+    fn layer_size_is_zero_only_on_stretched_axes() {
+        let bar = Anchor::TOP | Anchor::LEFT | Anchor::RIGHT;
+        assert_eq!(layer_size(bar, 0, 800, 30), (0, 30));
+        assert_eq!(layer_size(bar, 24, 800, 30), (0, 24));
+        let dock = Anchor::LEFT | Anchor::TOP | Anchor::BOTTOM;
+        assert_eq!(layer_size(dock, 48, 64, 600), (48, 0));
+        // A corner has no stretched axis, a zero size would be a protocol
+        // error
+        let corner = Anchor::TOP | Anchor::RIGHT;
+        assert_eq!(layer_size(corner, 0, 0, 0), (1, 1));
+        assert_eq!(layer_size(corner, 0, 200, 100), (200, 100));
+        let fill = Anchor::all();
+        assert_eq!(layer_size(fill, 30, 200, 100), (0, 0));
+        assert_eq!(layer_size(Anchor::empty(), 30, 200, 100), (200, 100));
+    }
+
+    #[test]
+    fn layer_surfaces_take_focus_with_interactivity() {
+        assert!(can_take_keyboard_focus(false, None));
+        assert!(!can_take_keyboard_focus(true, None));
+        assert!(!can_take_keyboard_focus(
+            true,
+            Some(KeyboardInteractivity::None)
+        ));
+        assert!(can_take_keyboard_focus(
+            true,
+            Some(KeyboardInteractivity::OnDemand)
+        ));
+        assert!(can_take_keyboard_focus(
+            true,
+            Some(KeyboardInteractivity::Exclusive)
+        ));
+    }
+
+    #[test]
+    #[ignore = "needs a Wayland compositor"]
+    fn configures_render_once() {
+        let mut app = Application::new(|_| {});
+        let (mut surface, configure) = configured_window(&mut app);
+
+        // E.g. an interactive resize sends several configures in one batch
+        app.take_wayland_events();
+        let events = [configure.clone(), configure];
+        surface.handle_events(&mut app, &events, &mut |_: &mut egui::Ui| {});
+        assert_eq!(app.last_dispatch_metrics().renders, 1);
+        assert_eq!(surface.frame_number(), 1);
+
+        app.take_wayland_events();
+        surface.handle_events(&mut app, &events[..1], &mut |_: &mut egui::Ui| {});
+        assert_eq!(app.last_dispatch_metrics().renders, 1);
+        assert_eq!(surface.frame_number(), 2);
+    }
+
+    #[test]
+    #[ignore = "needs a Wayland compositor"]
+    fn zero_configure_keeps_the_size() {
+        let mut app = Application::new(|_| {});
+        let (mut surface, mut configure) = configured_window(&mut app);
+        if let WaylandEvent::WindowConfigure(_, configure) = &mut configure {
+            configure.new_size = (None, None);
+        }
+        surface.handle_events(&mut app, &[configure], &mut |_: &mut egui::Ui| {});
+        assert_eq!(surface.size(), LogicalSize::new(200, 100));
+    }
+
+    #[test]
+    #[ignore = "needs a Wayland compositor"]
+    fn rapid_resize_applies_the_latest_configure() {
+        let mut app = Application::new(|_| {});
+        let (mut surface, configure) = configured_window(&mut app);
+        let resized = |width: u32, height: u32| {
+            let mut configure = configure.clone();
+            if let WaylandEvent::WindowConfigure(_, configure) = &mut configure {
+                configure.new_size = (NonZero::new(width), NonZero::new(height));
+            }
+            configure
+        };
+
+        for batch in 1..=100 {
+            let events: Vec<_> = (0..5)
+                .map(|i| resized(100 + batch * 3 + i, 50 + batch + i))
+                .collect();
+            surface.handle_events(&mut app, &events, &mut |_: &mut egui::Ui| {});
+            // A protocol error, e.g. from a zero or stale sized buffer, would
+            // fail the roundtrip
+            app.roundtrip().expect("Roundtrip failed");
+            app.take_wayland_events();
+            assert_eq!(
+                surface.size(),
+                LogicalSize::new(104 + batch * 3, 54 + batch)
+            );
+            assert_eq!(surface.frame_number(), batch as u64);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a Wayland compositor"]
+    fn min_frame_interval_drops_frames() {
+        let mut app = Application::new(|_| {});
+        let (mut surface, configure) = configured_window(&mut app);
+        surface.handle_events(&mut app, &[configure], &mut |_: &mut egui::Ui| {});
+        assert_eq!(surface.frame_number(), 1);
+
+        let frame = WaylandEvent::ScheduledFrame(surface.wl_surface().clone());
+        surface.set_min_frame_interval(Some(Duration::from_secs(10)));
+        for _ in 0..3 {
+            surface.handle_events(&mut app, &[frame.clone()], &mut |_: &mut egui::Ui| {});
+        }
+        assert_eq!(surface.frame_number(), 1);
+        // The dropped frames are rendered once the interval has passed
+        assert!(surface.next_frame_deadline().is_some());
+
+        surface.set_min_frame_interval(None);
+        surface.handle_events(&mut app, &[frame], &mut |_: &mut egui::Ui| {});
+        assert_eq!(surface.frame_number(), 2);
+    }
+
+    #[test]
+    #[ignore = "needs a Wayland compositor"]
+    fn pointer_moving_between_surfaces_resets_hover_and_cursor() {
+        let mut app = Application::new(|_| {});
+        let (mut a, configure_a) = configured_window(&mut app);
+        let (mut b, configure_b) = configured_window(&mut app);
+        let mut link = |ui: &mut egui::Ui| ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        let mut label = |_: &mut egui::Ui| {};
+        a.handle_events(&mut app, &[configure_a], &mut link);
+        b.handle_events(&mut app, &[configure_b], &mut label);
+
+        app.pointer_entered(a.wl_surface().clone());
+        let events = [
+            pointer_event(&a, PointerEventKind::Enter { serial: 1 }),
+            pointer_event(&a, PointerEventKind::Motion { time: 1 }),
+        ];
+        a.handle_events(&mut app, &events, &mut link);
+        assert_eq!(app.cursor(), Shape::Pointer);
+        assert!(hover_pos(&a).is_some());
+
+        // Leave of A and enter of B arrive in one pointer frame, the
+        // application handles both before the surfaces
+        app.pointer_entered(b.wl_surface().clone());
+        assert_eq!(app.cursor(), Shape::Default);
+        let events = [
+            pointer_event(&a, PointerEventKind::Leave { serial: 2 }),
+            pointer_event(&b, PointerEventKind::Enter { serial: 2 }),
+            pointer_event(&b, PointerEventKind::Motion { time: 2 }),
+        ];
+        a.handle_events(&mut app, &events, &mut link);
+        assert!(hover_pos(&a).is_none());
+        // A no longer has the pointer, its stale cursor isn't set
+        assert_eq!(app.cursor(), Shape::Default);
+        b.handle_events(&mut app, &events, &mut label);
+        assert!(hover_pos(&b).is_some());
+        assert_eq!(app.cursor(), Shape::Default);
+
+        b.handle_events(&mut app, &events[2..], &mut link);
+        assert_eq!(app.cursor(), Shape::Pointer);
+    }
+
+    #[test]
+    #[ignore = "needs a Wayland compositor"]
+    fn drop_destroys_the_surface_objects() {
+        let mut app = Application::new(|_| {});
+        for _ in 0..100 {
+            let (surface, _) = configured_window(&mut app);
+            let wl_surface = surface.wl_surface().clone();
+            let viewport = surface.viewport.clone();
+            let fractional_scale = surface.fractional_scale.clone();
+            drop(surface);
+            assert!(viewport.is_none_or(|viewport| !viewport.is_alive()));
+            assert!(fractional_scale.is_none_or(|scale| !scale.is_alive()));
+            assert!(!wl_surface.is_alive());
         }
+        // The compositor saw no requests to destroyed objects
+        app.roundtrip().expect("Roundtrip failed");
+    }
+
+    #[test]
+    #[ignore = "needs a Wayland compositor"]
+    fn failed_wgpu_init_returns_an_error() {
+        let mut app = Application::new(|_| {});
+        // A color format can never be the depth buffer, so this fails on a
+        // GPU as well as on CI without an adapter
+        let options = EguiRendererOptions {
+            depth_stencil_format: Some(wgpu::TextureFormat::Rgba8Unorm),
+            ..Default::default()
+        };
+        let result = EguiSurfaceState::try_new_with_options(
+            &app,
+            unconfigured_window(&app),
+            200,
+            100,
+            options,
+        );
+        assert!(matches!(
+            result,
+            Err(RendererError::DepthStencilFormat(
+                wgpu::TextureFormat::Rgba8Unorm
+            ))
+        ));
+        drop(result);
+
+        // The fallback renders on the GPU, or on the CPU without an adapter
+        let (mut surface, configure) = configured_window(&mut app);
+        surface.handle_events(&mut app, &[configure], &mut |_: &mut egui::Ui| {});
+        assert_eq!(surface.frame_number(), 1);
     }
 }
@@ -0,0 +1,125 @@
+//! Title bar widget for client side decorations
+
+use egui::Rect;
+use egui::Sense;
+use smithay_client_toolkit::reexports::csd_frame::WindowManagerCapabilities;
+
+/// Default height of `TitleBar`, in logical pixels
+pub const DEFAULT_TITLE_BAR_HEIGHT: f32 = 28.0;
+
+/// Window action requested by clicking or dragging a `TitleBar`
+///
+/// Apply with `EguiSurfaceState::apply_title_bar_action`, `Close` is left to
+/// the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TitleBarAction {
+    Move,
+    Minimize,
+    ToggleMaximize,
+    Close,
+}
+
+/// Result of showing a `TitleBar`
+#[derive(Debug, Clone, Copy)]
+pub struct TitleBarResponse {
+    pub action: Option<TitleBarAction>,
+    /// Area below the title bar for the content of the window
+    pub content_rect: Rect,
+}
+
+/// Title bar with a draggable area and minimize, maximize and close buttons
+///
+/// Buttons the compositor doesn't support are disabled, pass
+/// `EguiSurfaceState::capabilities`.
+pub struct TitleBar<'a> {
+    title: &'a str,
+    height: f32,
+    capabilities: WindowManagerCapabilities,
+    maximized: bool,
+}
+
+impl<'a> TitleBar<'a> {
+    pub fn new(title: &'a str) -> Self {
+        Self {
+            title,
+            height: DEFAULT_TITLE_BAR_HEIGHT,
+            capabilities: WindowManagerCapabilities::all(),
+            maximized: false,
+        }
+    }
+
+    /// Height reserved for the title bar above the content
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn capabilities(mut self, capabilities: WindowManagerCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Show the restore instead of the maximize button
+    pub fn maximized(mut self, maximized: bool) -> Self {
+        self.maximized = maximized;
+        self
+    }
+
+    /// Draw the title bar at the top of `ui`
+    pub fn show(self, ui: &mut egui::Ui) -> TitleBarResponse {
+        let rect = ui.max_rect();
+        let bar_rect = Rect::from_min_size(rect.min, egui::vec2(rect.width(), self.height));
+        let content_rect = Rect::from_min_max(egui::pos2(rect.min.x, bar_rect.max.y), rect.max);
+        let can_minimize = self
+            .capabilities
+            .contains(WindowManagerCapabilities::MINIMIZE);
+        let can_maximize = self
+            .capabilities
+            .contains(WindowManagerCapabilities::MAXIMIZE);
+        let mut action = None;
+
+        // Added first, so the buttons get the clicks on top of it
+        let bar = ui.interact(bar_rect, ui.id().with("title_bar"), Sense::click_and_drag());
+        if bar.drag_started() {
+            action = Some(TitleBarAction::Move);
+        }
+        if bar.double_clicked() && can_maximize {
+            action = Some(TitleBarAction::ToggleMaximize);
+        }
+
+        ui.scope_builder(egui::UiBuilder::new().max_rect(bar_rect), |ui| {
+            ui.horizontal_centered(|ui| {
+                ui.add_space(8.0);
+                ui.label(self.title);
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.button("🗙").on_hover_text("Close").clicked() {
+                        action = Some(TitleBarAction::Close);
+                    }
+                    let (icon, hint) = if self.maximized {
+                        ("🗗", "Restore")
+                    } else {
+                        ("🗖", "Maximize")
+                    };
+                    let maximize = ui.add_enabled(can_maximize, egui::Button::new(icon));
+                    if maximize.on_hover_text(hint).clicked() {
+                        action = Some(TitleBarAction::ToggleMaximize);
+                    }
+                    let minimize = ui.add_enabled(can_minimize, egui::Button::new("🗕"));
+                    if minimize.on_hover_text("Minimize").clicked() {
+                        action = Some(TitleBarAction::Minimize);
+                    }
+                });
+            });
+        });
+        ui.painter().hline(
+            bar_rect.x_range(),
+            bar_rect.max.y,
+            ui.visuals().widgets.noninteractive.bg_stroke,
+        );
+
+        TitleBarResponse {
+            action,
+            content_rect,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::PhysicalSize;
+use crate::Scale;
 use egui::Context;
 use egui_wgpu::Renderer;
 use egui_wgpu::RendererOptions;
@@ -9,11 +11,18 @@ use egui_wgpu::wgpu::StoreOp;
 use egui_wgpu::wgpu::Surface;
 use egui_wgpu::wgpu::SurfaceConfiguration;
 use egui_wgpu::wgpu::TextureFormat;
+use egui_wgpu::wgpu::util::StagingBelt;
 use raw_window_handle::RawDisplayHandle;
 use raw_window_handle::RawWindowHandle;
 use raw_window_handle::WaylandDisplayHandle;
 use raw_window_handle::WaylandWindowHandle;
+use smithay_client_toolkit::shm::CreatePoolError;
 use std::ptr::NonNull;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 use wayland_client::Connection;
 use wayland_client::Proxy;
 use wayland_client::protocol::wl_surface::WlSurface;
@@ -26,15 +35,38 @@ use wayland_client::protocol::wl_surface::WlSurface;
 //    egui Renderer would lose texture state and panic on the next frame.
 // 3. `resume()` recreates the wgpu Surface from the saved WlSurface, using the
 //    *same* Instance (a surface from a new Instance can't find the Device).
-//    Width/height are left at 0 to force a reconfigure on the next render.
-// 4. `render_to_wgpu()` acquires the next swapchain image, clears it, then
-//    draws the EGUI shapes / textures and presents. Skips silently if
-//    suspended.
+//    Size is left at 0x0 to force a reconfigure on the next render.
+// 4. `render_to_wgpu()` acquires the next swapchain image, clears it and draws
+//    the EGUI shapes / textures in a single render pass, then presents. Skips
+//    silently if suspended.
+// 5. With a GPU timing callback, the pass writes timestamps that are resolved
+//    and read back asynchronously. Frames rendered while the previous read back
+//    is still in flight are not timed.
+// 6. Paint callbacks upload through the `StagingBelt` in the callback
+//    resources. It's finished before the submit and recalled after it, the
+//    device poll at the start of the next frame returns its buffers for reuse.
+//    EGUI's own vertex, index and texture data goes through the queue's staging
+//    memory, `Renderer::update_buffers` doesn't take a belt.
+//
+// Presenting is already zero-copy on Mesa: its Vulkan and EGL Wayland WSI
+// share the swapchain images with the compositor as linux-dmabuf buffers, so
+// exporting WGPU textures as dmabufs by hand wouldn't save a copy.
+
+/// Size of the staging buffers of the belt paint callbacks upload through
+const STAGING_BELT_CHUNK_SIZE: wgpu::BufferAddress = 64 * 1024;
 
 /// WGPU renderer for EGUI.
+///
+/// Paint callbacks can upload per-frame data, e.g. uniforms, with the
+/// `wgpu::util::StagingBelt` in the callback resources: in
+/// `CallbackTrait::prepare` call `write_buffer` of
+/// `resources.get_mut::<StagingBelt>()` with the EGUI encoder. The staging
+/// buffers are reused across frames instead of allocated by the queue.
 pub struct EguiWgpuRenderer {
     egui_context: Context,
-    egui_renderer: Renderer,
+    // Shared with the renderers of the context's other viewports, so EGUI
+    // textures are uploaded once for all of them, see `try_for_surface`
+    egui_renderer: Arc<Mutex<Renderer>>,
 
     // Fields are dropped in declaration order. `wgpu_surface` must come before
     // `wgpu_device`, and `wgpu_device` before `wgpu_instance` — otherwise the
@@ -45,70 +77,351 @@ pub struct EguiWgpuRenderer {
     wgpu_queue: Queue,
     wgpu_surface_config: Option<SurfaceConfiguration>,
     wgpu_instance: wgpu::Instance,
+    wgpu_adapter: wgpu::Adapter,
     output_format: TextureFormat,
-    width: u32,
-    height: u32,
+    supported_usages: wgpu::TextureUsages,
+    extra_surface_usages: wgpu::TextureUsages,
+    size: PhysicalSize,
+    supports_timestamps: bool,
+    gpu_timing: Option<GpuTiming>,
+    clear_color: egui::Color32,
+    depth_stencil_format: Option<TextureFormat>,
+    depth_view: Option<wgpu::TextureView>,
     wl_surface: WlSurface,
     wl_conn: Connection,
 }
 
+/// WGPU initialization failure, e.g. no GPU or drivers available
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RendererError {
+    Surface(wgpu::CreateSurfaceError),
+    Adapter(wgpu::RequestAdapterError),
+    Device(wgpu::RequestDeviceError),
+    /// The adapter can't render to the requested depth-stencil format
+    DepthStencilFormat(TextureFormat),
+    /// The adapter given to `try_with_device` can't present to the surface
+    SurfaceNotSupported,
+    /// The shm pool of the software renderer couldn't be created
+    ShmPool(CreatePoolError),
+}
+
+impl std::fmt::Display for RendererError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RendererError::Surface(err) => write!(f, "Failed to create WGPU surface: {err}"),
+            RendererError::Adapter(err) => write!(f, "Failed to find a suitable adapter: {err}"),
+            RendererError::Device(err) => write!(f, "Failed to request WGPU device: {err}"),
+            RendererError::DepthStencilFormat(format) => {
+                write!(f, "Unsupported depth-stencil format: {format:?}")
+            }
+            RendererError::SurfaceNotSupported => {
+                write!(f, "WGPU adapter can't present to the Wayland surface")
+            }
+            RendererError::ShmPool(err) => write!(f, "Failed to create shm pool: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RendererError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RendererError::Surface(err) => Some(err),
+            RendererError::Adapter(err) => Some(err),
+            RendererError::Device(err) => Some(err),
+            RendererError::DepthStencilFormat(_) => None,
+            RendererError::SurfaceNotSupported => None,
+            RendererError::ShmPool(err) => Some(err),
+        }
+    }
+}
+
+/// WGPU device of an app, for rendering EGUI surfaces on it, see
+/// `EguiWgpuRenderer::try_with_device`
+///
+/// `instance` must be the instance `adapter` was requested from.
+#[derive(Debug, Clone)]
+pub struct WgpuContext {
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub device: Device,
+    pub queue: Queue,
+}
+
+/// Options for the WGPU adapter and device, set on the `Application`
+///
+/// Devices are created per surface and can't change their options afterwards,
+/// see `Application::set_gpu_power_preference`.
+#[derive(Debug, Clone)]
+pub(crate) struct DeviceOptions {
+    pub prefer_10bit: bool,
+    pub power_preference: wgpu::PowerPreference,
+    pub memory_hints: wgpu::MemoryHints,
+    pub renderer: EguiRendererOptions,
+}
+
+impl Default for DeviceOptions {
+    fn default() -> Self {
+        Self {
+            prefer_10bit: false,
+            power_preference: wgpu::PowerPreference::default(),
+            memory_hints: wgpu::MemoryHints::MemoryUsage,
+            renderer: EguiRendererOptions::default(),
+        }
+    }
+}
+
+/// Options of the EGUI renderer of a surface, see
+/// `EguiSurfaceState::try_new_with_options`
+#[derive(Debug, Clone, Copy)]
+pub struct EguiRendererOptions {
+    /// Dither colors to avoid banding in gradients, turn off e.g. for
+    /// pixel exact screenshots. Defaults to `true`.
+    pub dithering: bool,
+    /// Depth-stencil format of the render pass, for paint callbacks drawing a
+    /// depth tested 3D scene. The depth buffer is cleared to `1.0` each frame.
+    pub depth_stencil_format: Option<TextureFormat>,
+}
+
+impl Default for EguiRendererOptions {
+    fn default() -> Self {
+        Self {
+            dithering: true,
+            depth_stencil_format: None,
+        }
+    }
+}
+
 impl EguiWgpuRenderer {
+    /// Create the renderer, panics if WGPU can't be initialized
     pub fn new(
         egui_context: &Context,
         wl_surface: &WlSurface,
         conn: &Connection,
     ) -> EguiWgpuRenderer {
+        Self::try_new(egui_context, wl_surface, conn).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Create the renderer, or an error if WGPU can't be initialized
+    pub fn try_new(
+        egui_context: &Context,
+        wl_surface: &WlSurface,
+        conn: &Connection,
+    ) -> Result<EguiWgpuRenderer, RendererError> {
+        futures::executor::block_on(Self::try_new_async(egui_context, wl_surface, conn))
+    }
+
+    /// Create the renderer without blocking on the WGPU adapter and device
+    /// requests, e.g. in an async runtime
+    pub async fn try_new_async(
+        egui_context: &Context,
+        wl_surface: &WlSurface,
+        conn: &Connection,
+    ) -> Result<EguiWgpuRenderer, RendererError> {
+        Self::create(egui_context, wl_surface, conn, &DeviceOptions::default()).await
+    }
+
+    /// Create the renderer, with `prefer_10bit` of the options choosing
+    /// `Rgb10a2Unorm` over the default surface format when the surface
+    /// supports it
+    pub(crate) async fn create(
+        egui_context: &Context,
+        wl_surface: &WlSurface,
+        conn: &Connection,
+        options: &DeviceOptions,
+    ) -> Result<EguiWgpuRenderer, RendererError> {
+        // Only a format with a depth aspect can be the depth buffer, checked
+        // before the adapter so the error doesn't depend on the GPU
+        if let Some(format) = options.renderer.depth_stencil_format
+            && !format.has_depth_aspect()
+        {
+            return Err(RendererError::DepthStencilFormat(format));
+        }
+
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
             ..wgpu::InstanceDescriptor::new_without_display_handle()
         });
 
-        let surface = Self::create_wgpu_surface(&instance, conn, wl_surface);
+        let surface = Self::create_wgpu_surface(&instance, conn, wl_surface)
+            .map_err(RendererError::Surface)?;
 
-        let adapter =
-            futures::executor::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
                 compatible_surface: Some(&surface),
+                power_preference: options.power_preference,
                 ..Default::default()
-            }))
-            .expect("Failed to find a suitable adapter");
+            })
+            .await
+            .map_err(RendererError::Adapter)?;
 
-        let (wgpu_device, wgpu_queue) =
-            futures::executor::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
-                memory_hints: wgpu::MemoryHints::MemoryUsage,
+        // Timestamp queries are requested when available, they're only used
+        // with a GPU timing callback
+        let timestamp_features = adapter.features() & wgpu::Features::TIMESTAMP_QUERY;
+        let (wgpu_device, wgpu_queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                required_features: timestamp_features,
+                memory_hints: options.memory_hints.clone(),
                 ..Default::default()
-            }))
-            .expect("Failed to request WGPU device");
+            })
+            .await
+            .map_err(RendererError::Device)?;
+
+        let gpu = WgpuContext {
+            instance,
+            adapter,
+            device: wgpu_device,
+            queue: wgpu_queue,
+        };
+        Self::from_device(egui_context, wl_surface, conn, surface, &gpu, options)
+    }
 
-        let caps = surface.get_capabilities(&adapter);
-        let output_format = *caps
+    /// Create the renderer on an existing WGPU device, e.g. one shared with
+    /// another renderer of the app
+    ///
+    /// The adapter must be able to present to Wayland surfaces: the surface
+    /// is created with the given instance, and the adapter has to support it
+    /// with at least one format and the `RENDER_ATTACHMENT` usage, otherwise
+    /// this fails with `RendererError::SurfaceNotSupported`. Timestamp
+    /// queries for `set_gpu_timing_callback` are used if the device was
+    /// created with `Features::TIMESTAMP_QUERY`.
+    pub fn try_with_device(
+        egui_context: &Context,
+        wl_surface: &WlSurface,
+        conn: &Connection,
+        gpu: &WgpuContext,
+        options: EguiRendererOptions,
+    ) -> Result<EguiWgpuRenderer, RendererError> {
+        let surface = Self::create_wgpu_surface(&gpu.instance, conn, wl_surface)
+            .map_err(RendererError::Surface)?;
+        let options = DeviceOptions {
+            renderer: options,
+            ..DeviceOptions::default()
+        };
+        Self::from_device(egui_context, wl_surface, conn, surface, gpu, &options)
+    }
+
+    fn from_device(
+        egui_context: &Context,
+        wl_surface: &WlSurface,
+        conn: &Connection,
+        surface: Surface<'static>,
+        gpu: &WgpuContext,
+        options: &DeviceOptions,
+    ) -> Result<EguiWgpuRenderer, RendererError> {
+        let adapter = &gpu.adapter;
+        let wgpu_device = gpu.device.clone();
+        let caps = surface.get_capabilities(adapter);
+        let presentable = adapter.is_surface_supported(&surface)
+            && !caps.formats.is_empty()
+            && caps.usages.contains(wgpu::TextureUsages::RENDER_ATTACHMENT);
+        if !presentable {
+            return Err(RendererError::SurfaceNotSupported);
+        }
+
+        let depth_stencil_format = options.renderer.depth_stencil_format;
+        if let Some(format) = depth_stencil_format {
+            let usable = format.has_depth_aspect()
+                && adapter
+                    .get_texture_format_features(format)
+                    .allowed_usages
+                    .contains(wgpu::TextureUsages::RENDER_ATTACHMENT);
+            if !usable {
+                return Err(RendererError::DepthStencilFormat(format));
+            }
+        }
+
+        let wide_format = caps
             .formats
-            .get(0)
+            .iter()
+            .find(|format| options.prefer_10bit && **format == wgpu::TextureFormat::Rgb10a2Unorm);
+        let output_format = *wide_format
+            .or(caps.formats.first())
             .unwrap_or(&wgpu::TextureFormat::Bgra8Unorm);
+        log::trace!("[EGUI] Using surface format {:?}", output_format);
 
-        let egui_renderer = Renderer::new(
+        let supported_usages = caps.usages;
+        let supports_timestamps = wgpu_device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY);
+
+        let mut egui_renderer = Renderer::new(
             &wgpu_device,
             output_format,
             RendererOptions {
                 msaa_samples: 1,
-                depth_stencil_format: None,
+                depth_stencil_format,
+                dithering: options.renderer.dithering,
                 ..Default::default()
             },
         );
+        egui_renderer.callback_resources.insert(StagingBelt::new(
+            wgpu_device.clone(),
+            STAGING_BELT_CHUNK_SIZE,
+        ));
 
-        EguiWgpuRenderer {
+        Ok(EguiWgpuRenderer {
             egui_context: egui_context.clone(),
-            egui_renderer,
+            egui_renderer: Arc::new(Mutex::new(egui_renderer)),
             wgpu_surface: Some(surface),
             wgpu_device,
-            wgpu_queue,
+            wgpu_queue: gpu.queue.clone(),
             wgpu_surface_config: None,
             output_format,
-            width: 0,
-            height: 0,
+            supported_usages,
+            extra_surface_usages: wgpu::TextureUsages::empty(),
+            size: PhysicalSize::default(),
+            supports_timestamps,
+            gpu_timing: None,
+            clear_color: egui::Color32::TRANSPARENT,
+            depth_stencil_format,
+            depth_view: None,
             wl_surface: wl_surface.clone(),
             wl_conn: conn.clone(),
-            wgpu_instance: instance,
+            wgpu_instance: gpu.instance.clone(),
+            wgpu_adapter: gpu.adapter.clone(),
+        })
+    }
+
+    /// Create a renderer for another surface of the same EGUI context, e.g.
+    /// the window of a deferred viewport
+    ///
+    /// The renderers share the device and the EGUI textures, so each texture
+    /// delta only needs to be rendered by one of them. Fails with
+    /// `RendererError::SurfaceNotSupported` if the surface can't use the
+    /// output format of this renderer.
+    pub fn try_for_surface(&self, wl_surface: &WlSurface) -> Result<Self, RendererError> {
+        let surface = Self::create_wgpu_surface(&self.wgpu_instance, &self.wl_conn, wl_surface)
+            .map_err(RendererError::Surface)?;
+        let caps = surface.get_capabilities(&self.wgpu_adapter);
+        let presentable = self.wgpu_adapter.is_surface_supported(&surface)
+            && caps.formats.contains(&self.output_format)
+            && caps.usages.contains(wgpu::TextureUsages::RENDER_ATTACHMENT);
+        if !presentable {
+            return Err(RendererError::SurfaceNotSupported);
         }
+
+        Ok(EguiWgpuRenderer {
+            egui_context: self.egui_context.clone(),
+            egui_renderer: self.egui_renderer.clone(),
+            wgpu_surface: Some(surface),
+            wgpu_device: self.wgpu_device.clone(),
+            wgpu_queue: self.wgpu_queue.clone(),
+            wgpu_surface_config: None,
+            output_format: self.output_format,
+            supported_usages: caps.usages,
+            extra_surface_usages: wgpu::TextureUsages::empty(),
+            size: PhysicalSize::default(),
+            supports_timestamps: self.supports_timestamps,
+            gpu_timing: None,
+            clear_color: self.clear_color,
+            depth_stencil_format: self.depth_stencil_format,
+            depth_view: None,
+            wl_surface: wl_surface.clone(),
+            wl_conn: self.wl_conn.clone(),
+            wgpu_instance: self.wgpu_instance.clone(),
+            wgpu_adapter: self.wgpu_adapter.clone(),
+        })
     }
 
     /// Suspend the renderer — drops the WGPU surface and configuration to
@@ -119,30 +432,116 @@ impl EguiWgpuRenderer {
             log::trace!("[EGUI] Suspending WGPU surface to free GPU resources");
             self.wgpu_surface = None;
             self.wgpu_surface_config = None;
-            self.width = 0;
-            self.height = 0;
+            self.size = PhysicalSize::default();
         }
     }
 
+    /// Set the color the surface is cleared with before EGUI draws
+    pub fn set_clear_color(&mut self, color: egui::Color32) {
+        self.clear_color = color;
+    }
+
+    /// Clear color as WGPU expects it for the output format
+    fn wgpu_clear_color(&self) -> wgpu::Color {
+        // Color32 is premultiplied gamma, sRGB formats take linear colors
+        let [r, g, b, a] = if self.output_format.is_srgb() {
+            egui::Rgba::from(self.clear_color).to_array()
+        } else {
+            self.clear_color.to_normalized_gamma_f32()
+        };
+        wgpu::Color {
+            r: r as f64,
+            g: g as f64,
+            b: b as f64,
+            a: a as f64,
+        }
+    }
+
+    /// Reconfigure the WGPU surface on the next render even if the size
+    /// didn't change
+    pub fn force_reconfigure(&mut self) {
+        self.size = PhysicalSize::default();
+    }
+
     /// Resume the renderer — recreates the WGPU surface from the saved
     /// Wayland surface handle, using the same Instance that owns the Device.
-    pub fn resume(&mut self) {
+    ///
+    /// Stays suspended if the surface can't be created.
+    pub fn resume(&mut self) -> Result<(), RendererError> {
         if self.wgpu_surface.is_none() {
             log::trace!("[EGUI] Resuming WGPU surface");
-            self.wgpu_surface = Some(Self::create_wgpu_surface(
-                &self.wgpu_instance,
-                &self.wl_conn,
-                &self.wl_surface,
-            ));
+            let surface =
+                Self::create_wgpu_surface(&self.wgpu_instance, &self.wl_conn, &self.wl_surface)
+                    .map_err(RendererError::Surface)?;
+            self.wgpu_surface = Some(surface);
+        }
+        Ok(())
+    }
+
+    /// Set extra usages for the swapchain textures, e.g. `COPY_SRC` for
+    /// reading back rendered frames.
+    ///
+    /// Usages not supported by the surface are dropped with a warning, check
+    /// the result with `surface_usages()`. Support depends on the driver
+    /// rather than the compositor: Mesa Vulkan drivers usually allow
+    /// `COPY_SRC` on Wayland swapchains, while the GL backend typically only
+    /// allows `RENDER_ATTACHMENT`. The renderer doesn't read frames back
+    /// itself, so there is no fallback when the swapchain can't be
+    /// `COPY_SRC`: render the UI to a texture instead, e.g. with
+    /// `render_ui_to_rgba`.
+    pub fn set_extra_surface_usages(&mut self, usages: wgpu::TextureUsages) {
+        let unsupported = usages - self.supported_usages;
+        if !unsupported.is_empty() {
+            log::warn!(
+                "[EGUI] Surface doesn't support texture usages {:?}, ignoring them",
+                unsupported
+            );
+        }
+        let usages = usages & self.supported_usages;
+        if usages != self.extra_surface_usages {
+            self.extra_surface_usages = usages;
+            // Force reconfigure on the next render
+            self.size = PhysicalSize::default();
         }
     }
 
+    /// Texture format of the swapchain, fixed when the renderer is created
+    pub fn output_format(&self) -> TextureFormat {
+        self.output_format
+    }
+
+    /// Texture usages the swapchain is configured with
+    pub fn surface_usages(&self) -> wgpu::TextureUsages {
+        wgpu::TextureUsages::RENDER_ATTACHMENT | self.extra_surface_usages
+    }
+
+    /// Set a callback receiving the GPU time of each rendered frame
+    ///
+    /// Returns `false` if the adapter doesn't support timestamp queries, in
+    /// which case the callback is never called. The callback runs when WGPU
+    /// maps the timestamps, during a later render.
+    pub fn set_gpu_timing_callback(
+        &mut self,
+        callback: impl FnMut(Duration) + Send + 'static,
+    ) -> bool {
+        if !self.supports_timestamps {
+            log::warn!("[EGUI] GPU timing not available, TIMESTAMP_QUERY is not supported");
+            return false;
+        }
+        self.gpu_timing = Some(GpuTiming::new(
+            &self.wgpu_device,
+            self.wgpu_queue.get_timestamp_period(),
+            Box::new(callback),
+        ));
+        true
+    }
+
     /// Create a WGPU surface from Wayland connection and surface.
     fn create_wgpu_surface(
         instance: &wgpu::Instance,
         conn: &Connection,
         wl_surface: &WlSurface,
-    ) -> Surface<'static> {
+    ) -> Result<Surface<'static>, wgpu::CreateSurfaceError> {
         let raw_display_handle = RawDisplayHandle::Wayland(WaylandDisplayHandle::new(
             NonNull::new(conn.backend().display_ptr() as *mut _)
                 .expect("Wayland display pointer was null"),
@@ -160,26 +559,22 @@ impl EguiWgpuRenderer {
         // requires HasDisplayHandle + HasWindowHandle, which Wayland
         // proxy types don't implement.
         unsafe {
-            instance
-                .create_surface_unsafe(wgpu::SurfaceTargetUnsafe::RawHandle {
-                    raw_display_handle: Some(raw_display_handle),
-                    raw_window_handle,
-                })
-                .expect("Failed to create WGPU surface")
+            instance.create_surface_unsafe(wgpu::SurfaceTargetUnsafe::RawHandle {
+                raw_display_handle: Some(raw_display_handle),
+                raw_window_handle,
+            })
         }
     }
 
     /// Resize and reconfigure the WGPU surface
-    fn reconfigure_surface(&mut self, width: u32, height: u32) {
-        let width = width.max(1);
-        let height = height.max(1);
-        self.width = width;
-        self.height = height;
+    fn reconfigure_surface(&mut self, size: PhysicalSize) {
+        let size = size.at_least_one();
+        self.size = size;
         let config = SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage: self.surface_usages(),
             format: self.output_format,
-            width,
-            height,
+            width: size.width,
+            height: size.height,
             present_mode: wgpu::PresentMode::Mailbox,
             alpha_mode: wgpu::CompositeAlphaMode::PreMultiplied,
             view_formats: vec![self.output_format],
@@ -189,6 +584,24 @@ impl EguiWgpuRenderer {
             surface.configure(&self.wgpu_device, &config);
         }
         self.wgpu_surface_config = Some(config);
+        self.depth_view = self.depth_stencil_format.map(|format| {
+            self.wgpu_device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some("egui depth-stencil"),
+                    size: wgpu::Extent3d {
+                        width: size.width,
+                        height: size.height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        });
     }
 
     /// Acquire the next surface texture
@@ -217,11 +630,21 @@ impl EguiWgpuRenderer {
                 }
                 wgpu::CurrentSurfaceTexture::Lost => {
                     log::warn!("[EGUI] Surface was lost, recreating and reconfiguring");
-                    self.wgpu_surface = Some(Self::create_wgpu_surface(
+                    match Self::create_wgpu_surface(
                         &self.wgpu_instance,
                         &self.wl_conn,
                         &self.wl_surface,
-                    ));
+                    ) {
+                        Ok(surface) => self.wgpu_surface = Some(surface),
+                        Err(err) => {
+                            // The lost surface is kept, the next frame tries
+                            // again
+                            log::error!(
+                                "[EGUI] Failed to recreate WGPU surface: {err}, skipping frame"
+                            );
+                            return None;
+                        }
+                    }
 
                     // TODO: From the docs, quote:
                     //
@@ -247,7 +670,7 @@ impl EguiWgpuRenderer {
             }
         }
 
-        self.reconfigure_surface(self.width, self.height);
+        self.reconfigure_surface(self.size);
 
         let surface = match self.wgpu_surface.as_ref() {
             Some(surface) => surface,
@@ -278,20 +701,18 @@ impl EguiWgpuRenderer {
     pub fn render_to_wgpu(
         &mut self,
         egui_fulloutput: egui::FullOutput,
-        width: u32,
-        height: u32,
-        pixels_per_point: f32,
+        size: PhysicalSize,
+        scale: Scale,
     ) {
         // EGUI Screen descriptor for this frame
         let screen_descriptor = ScreenDescriptor {
-            size_in_pixels: [width, height],
-            pixels_per_point,
+            size_in_pixels: [size.width, size.height],
+            pixels_per_point: scale.get(),
         };
 
         // Reconfigure if size changed (must happen before borrowing surface)
-        let needs_reconfig = (width != self.width) || (height != self.height);
-        if needs_reconfig {
-            self.reconfigure_surface(width, height);
+        if size != self.size {
+            self.reconfigure_surface(size);
         }
 
         let surface_texture = match self.acquire_surface_texture() {
@@ -306,39 +727,23 @@ impl EguiWgpuRenderer {
             .create_view(&wgpu::TextureViewDescriptor::default());
         let mut encoder = self.wgpu_device.create_command_encoder(&Default::default());
 
-        // Clear pass
-        let _ = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("egui clear pass"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &texture_view,
-                resolve_target: None,
-                depth_slice: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
-            occlusion_query_set: None,
-            multiview_mask: None,
-        });
+        // Deliver timings of previous frames, and return the staging buffers
+        // the GPU is done with to the belt
+        let _ = self.wgpu_device.poll(wgpu::PollType::Poll);
+        let gpu_timing = self.gpu_timing.as_ref().filter(|timing| timing.is_ready());
 
         // Draw EGUI shapes with WGPU
         let tris = self
             .egui_context
             .tessellate(egui_fulloutput.shapes, egui_fulloutput.pixels_per_point);
 
+        let mut egui_renderer = self.egui_renderer.lock().unwrap();
         for (id, image_delta) in &egui_fulloutput.textures_delta.set {
-            self.egui_renderer.update_texture(
-                &self.wgpu_device,
-                &self.wgpu_queue,
-                *id,
-                image_delta,
-            );
+            egui_renderer.update_texture(&self.wgpu_device, &self.wgpu_queue, *id, image_delta);
         }
 
-        self.egui_renderer.update_buffers(
+        // Command buffers of paint callbacks, they run before the EGUI pass
+        let callback_buffers = egui_renderer.update_buffers(
             &self.wgpu_device,
             &self.wgpu_queue,
             &mut encoder,
@@ -346,33 +751,150 @@ impl EguiWgpuRenderer {
             &screen_descriptor,
         );
 
-        // Render pass to draw EGUI output to the surface
+        // Render pass to draw EGUI output to the surface, clearing happens in
+        // the same pass to avoid a separate clear pass per frame
+        let clear_color = self.wgpu_clear_color();
         let rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                 view: &texture_view,
                 resolve_target: None,
                 depth_slice: None,
                 ops: egui_wgpu::wgpu::Operations {
-                    load: egui_wgpu::wgpu::LoadOp::Load,
+                    load: egui_wgpu::wgpu::LoadOp::Clear(clear_color),
                     store: StoreOp::Store,
                 },
             })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
+            depth_stencil_attachment: self.depth_view.as_ref().map(|view| {
+                wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: StoreOp::Discard,
+                    }),
+                    stencil_ops: self
+                        .depth_stencil_format
+                        .filter(|format| format.has_stencil_aspect())
+                        .map(|_| wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(0),
+                            store: StoreOp::Discard,
+                        }),
+                }
+            }),
+            timestamp_writes: gpu_timing.map(|timing| wgpu::RenderPassTimestampWrites {
+                query_set: &timing.query_set,
+                beginning_of_pass_write_index: Some(0),
+                end_of_pass_write_index: Some(1),
+            }),
             label: Some("egui main render pass"),
             occlusion_query_set: None,
             multiview_mask: None,
         });
 
         // Cleanup any textures marked for deletion by EGUI before rendering
-        self.egui_renderer
-            .render(&mut rpass.forget_lifetime(), &tris, &screen_descriptor);
+        egui_renderer.render(&mut rpass.forget_lifetime(), &tris, &screen_descriptor);
         for x in &egui_fulloutput.textures_delta.free {
-            self.egui_renderer.free_texture(x)
+            egui_renderer.free_texture(x)
+        }
+
+        if let Some(timing) = gpu_timing {
+            timing.resolve(&mut encoder);
         }
 
         // Submit commands and present
-        self.wgpu_queue.submit(Some(encoder.finish()));
+        if let Some(staging_belt) = egui_renderer.callback_resources.get_mut::<StagingBelt>() {
+            staging_belt.finish();
+        }
+        self.wgpu_queue
+            .submit(callback_buffers.into_iter().chain([encoder.finish()]));
+        if let Some(staging_belt) = egui_renderer.callback_resources.get_mut::<StagingBelt>() {
+            staging_belt.recall();
+        }
         surface_texture.present();
+
+        if let Some(timing) = gpu_timing {
+            timing.read_back();
+        }
+    }
+}
+
+/// Timestamp queries around the EGUI render pass
+struct GpuTiming {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    timestamp_period: f32,
+    mapping: Arc<AtomicBool>,
+    callback: Arc<Mutex<GpuTimingCallback>>,
+}
+
+/// Callback given to `set_gpu_timing_callback`
+type GpuTimingCallback = Box<dyn FnMut(Duration) + Send>;
+
+impl GpuTiming {
+    const SIZE: u64 = 2 * std::mem::size_of::<u64>() as u64;
+
+    fn new(device: &Device, timestamp_period: f32, callback: GpuTimingCallback) -> Self {
+        GpuTiming {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("egui timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2,
+            }),
+            resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("egui timestamps resolve"),
+                size: Self::SIZE,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            readback_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("egui timestamps readback"),
+                size: Self::SIZE,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            timestamp_period,
+            mapping: Arc::new(AtomicBool::new(false)),
+            callback: Arc::new(Mutex::new(callback)),
+        }
+    }
+
+    /// Readback buffer is free, timestamps can be written this frame
+    fn is_ready(&self) -> bool {
+        !self.mapping.load(Ordering::Acquire)
+    }
+
+    fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            Self::SIZE,
+        );
+    }
+
+    fn read_back(&self) {
+        self.mapping.store(true, Ordering::Release);
+        let buffer = self.readback_buffer.clone();
+        let mapping = self.mapping.clone();
+        let callback = self.callback.clone();
+        let timestamp_period = self.timestamp_period;
+        self.readback_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if result.is_ok() {
+                    let ticks = {
+                        let data = buffer.slice(..).get_mapped_range();
+                        let start = u64::from_le_bytes(data[0..8].try_into().unwrap());
+                        let end = u64::from_le_bytes(data[8..16].try_into().unwrap());
+                        end.saturating_sub(start)
+                    };
+                    buffer.unmap();
+                    let nanos = ticks as f64 * timestamp_period as f64;
+                    (callback.lock().unwrap())(Duration::from_nanos(nanos as u64));
+                }
+                mapping.store(false, Ordering::Release);
+            });
     }
 }
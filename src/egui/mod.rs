@@ -1,6 +1,22 @@
+#[cfg(feature = "accesskit")]
+mod egui_accesskit;
 mod egui_input_handler;
+mod egui_notification_stack;
+mod egui_offscreen;
+mod egui_per_output;
+mod egui_pointer_tooltip;
+mod egui_run;
+mod egui_software_renderer;
 mod egui_surface_state;
+mod egui_titlebar;
 mod egui_wgpu_renderer;
+#[cfg(feature = "accesskit")]
+pub(crate) use egui_accesskit::*;
 pub use egui_input_handler::*;
+pub use egui_notification_stack::*;
+pub use egui_offscreen::*;
+pub use egui_pointer_tooltip::*;
+pub use egui_software_renderer::*;
 pub use egui_surface_state::*;
+pub use egui_titlebar::*;
 pub use egui_wgpu_renderer::*;
@@ -0,0 +1,210 @@
+//! Bounded buffer of input events
+//!
+//! Used for the Wayland events waiting for the next dispatch, and for the
+//! EGUI input of each surface. Consecutive pointer motions are coalesced into
+//! the latest. When the buffer is full, the oldest pointer motion is dropped,
+//! or the new one if there is none. Other events (configures, keys, buttons)
+//! carry state and are never dropped, so the cap only bounds the bulk of a
+//! slow frame.
+
+use std::collections::VecDeque;
+
+/// Event kinds of an `EventBuffer`
+pub(crate) trait BufferedEvent {
+    /// Pointer motion, which can be coalesced or dropped
+    fn is_motion(&self) -> bool;
+
+    /// Whether this motion replaces the motion `previous` right before it
+    fn replaces(&self, _previous: &Self) -> bool {
+        true
+    }
+}
+
+pub(crate) struct EventBuffer<E> {
+    events: VecDeque<E>,
+    /// Motions in `events`, so a full buffer without any isn't searched
+    motions: usize,
+    max: usize,
+}
+
+impl<E: BufferedEvent> EventBuffer<E> {
+    pub fn new(max: usize) -> Self {
+        Self {
+            events: VecDeque::new(),
+            motions: 0,
+            max: max.max(1),
+        }
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Limit the buffered events, at least one. Events already buffered are
+    /// kept until taken.
+    pub fn set_max(&mut self, max: usize) {
+        self.max = max.max(1);
+    }
+
+    pub fn push(&mut self, event: E) {
+        let is_motion = event.is_motion();
+        if is_motion
+            && let Some(last) = self.events.back_mut()
+            && last.is_motion()
+            && event.replaces(last)
+        {
+            *last = event;
+            return;
+        }
+        if self.events.len() >= self.max {
+            if self.motions == 0 {
+                if is_motion {
+                    return;
+                }
+            } else if let Some(index) = self.events.iter().position(E::is_motion) {
+                // Motions are usually near the front, where removing is cheap
+                self.events.remove(index);
+                self.motions -= 1;
+            }
+        }
+        if is_motion {
+            self.motions += 1;
+        }
+        self.events.push_back(event);
+    }
+
+    /// Take the buffered events into a new `Vec`, none is allocated when
+    /// empty, see `drain_into`
+    pub fn take(&mut self) -> Vec<E> {
+        let mut events = Vec::new();
+        self.drain_into(&mut events);
+        events
+    }
+
+    /// Move the buffered events to the end of `out`
+    ///
+    /// The buffer keeps its allocation, so neither it nor a retained `out`
+    /// grows again for a batch like the previous one.
+    pub fn drain_into(&mut self, out: &mut Vec<E>) {
+        self.motions = 0;
+        out.extend(self.events.drain(..));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TestEvent::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum TestEvent {
+        Motion(u32, i32),
+        Key(i32),
+    }
+
+    impl BufferedEvent for TestEvent {
+        fn is_motion(&self) -> bool {
+            matches!(self, TestEvent::Motion(..))
+        }
+
+        /// Motions of the same surface are coalesced
+        fn replaces(&self, previous: &Self) -> bool {
+            matches!(
+                (self, previous),
+                (TestEvent::Motion(a, _), TestEvent::Motion(b, _)) if a == b
+            )
+        }
+    }
+
+    #[test]
+    fn consecutive_motions_are_coalesced() {
+        let mut buffer = EventBuffer::new(16);
+        for event in [
+            Motion(0, 1),
+            Motion(0, 2),
+            Key(1),
+            Motion(0, 3),
+            Motion(0, 4),
+        ] {
+            buffer.push(event);
+        }
+        assert_eq!(buffer.take(), [Motion(0, 2), Key(1), Motion(0, 4)]);
+    }
+
+    #[test]
+    fn motions_of_other_surfaces_are_kept() {
+        let mut buffer = EventBuffer::new(16);
+        for event in [Motion(0, 1), Motion(1, 2), Motion(1, 3)] {
+            buffer.push(event);
+        }
+        assert_eq!(buffer.take(), [Motion(0, 1), Motion(1, 3)]);
+    }
+
+    #[test]
+    fn full_buffer_drops_the_oldest_motion() {
+        let mut buffer = EventBuffer::new(3);
+        for event in [Key(1), Motion(0, 1), Key(2), Motion(1, 2)] {
+            buffer.push(event);
+        }
+        assert_eq!(buffer.take(), [Key(1), Key(2), Motion(1, 2)]);
+    }
+
+    #[test]
+    fn full_buffer_never_drops_other_events() {
+        let mut buffer = EventBuffer::new(2);
+        for event in [Key(1), Key(2), Motion(0, 1), Key(3)] {
+            buffer.push(event);
+        }
+        // The new motion is dropped, keys go past the limit
+        assert_eq!(buffer.take(), [Key(1), Key(2), Key(3)]);
+    }
+
+    #[test]
+    fn take_resets_the_motion_count() {
+        let mut buffer = EventBuffer::new(2);
+        buffer.push(Motion(0, 1));
+        buffer.take();
+        buffer.push(Key(1));
+        buffer.push(Key(2));
+        buffer.push(Motion(0, 2));
+        assert_eq!(buffer.take(), [Key(1), Key(2)]);
+    }
+
+    #[test]
+    fn drain_into_reuses_both_buffers() {
+        let mut buffer = EventBuffer::new(4096);
+        let mut out = Vec::new();
+        let mut pointers = None;
+        for frame in 0..100 {
+            for key in 0..32 {
+                buffer.push(Key(key));
+            }
+            let events = buffer.events.as_slices().0.as_ptr();
+            out.clear();
+            buffer.drain_into(&mut out);
+            assert_eq!(out.len(), 32);
+            let current = (events, out.as_ptr());
+            if let Some(pointers) = pointers {
+                assert_eq!(current, pointers, "frame {frame}");
+            }
+            pointers = Some(current);
+        }
+    }
+
+    #[test]
+    fn take_keeps_the_capacity_over_frames() {
+        let mut buffer = EventBuffer::new(4096);
+        let mut capacity = None;
+        for frame in 0..1000 {
+            for key in 0..32 {
+                buffer.push(Key(key));
+            }
+            // A batch like the previous one doesn't grow the buffer
+            if let Some(capacity) = capacity {
+                assert_eq!(buffer.events.capacity(), capacity, "frame {frame}");
+            }
+            assert_eq!(buffer.take().len(), 32);
+            capacity = Some(buffer.events.capacity());
+        }
+    }
+}
@@ -20,6 +20,9 @@ enum FrameSchedulerSignal {
     /// New earlier frame scheduled
     ScheduleFrameAt(Instant),
 
+    /// Clear the scheduled frame
+    Cancel,
+
     /// Exit the frame scheduler thread
     Exit,
 }
@@ -58,6 +61,9 @@ impl FrameScheduler {
                         Ok(FrameSchedulerSignal::ScheduleFrameAt(new_deadline)) => {
                             current_deadline_thrd.lock().unwrap().replace(new_deadline);
                         }
+                        Ok(FrameSchedulerSignal::Cancel) => {
+                            current_deadline_thrd.lock().unwrap().take();
+                        }
                         Err(RecvTimeoutError::Timeout) => {
                             current_deadline_thrd.lock().unwrap().take();
                             emit_frame();
@@ -123,6 +129,23 @@ impl FrameScheduler {
     pub fn schedule_frame(&mut self, at: Duration) {
         Self::schedule_frame_at(&self.fps_target, &self.current_deadline, &self.sender, at);
     }
+
+    /// Deadline of the scheduled frame, if any
+    ///
+    /// Updated by the scheduler thread, so a frame scheduled just now may not
+    /// be visible yet.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        *self.current_deadline.lock().unwrap()
+    }
+
+    /// Clear the scheduled frame, frames scheduled after this are kept
+    pub fn cancel(&mut self) {
+        // Cleared here too, the scheduler thread may not have handled the
+        // signal before the next frame is scheduled, which would then be
+        // dropped as later than the cancelled one
+        self.current_deadline.lock().unwrap().take();
+        let _ = self.sender.send(FrameSchedulerSignal::Cancel);
+    }
 }
 
 impl Drop for FrameScheduler {
@@ -131,3 +154,38 @@ impl Drop for FrameScheduler {
         self.thread.take().unwrap().join().unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::Receiver;
+
+    fn scheduler() -> (FrameScheduler, Receiver<()>) {
+        let (tx, rx) = channel();
+        let mut scheduler = FrameScheduler::new(move || {
+            let _ = tx.send(());
+        });
+        scheduler.set_fps_target(1000.0);
+        (scheduler, rx)
+    }
+
+    #[test]
+    fn cancel_drops_the_frame() {
+        let (mut scheduler, rx) = scheduler();
+        scheduler.schedule_frame(Duration::from_millis(50));
+        scheduler.cancel();
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+
+    #[test]
+    fn frame_scheduled_after_cancel_is_kept() {
+        let (mut scheduler, rx) = scheduler();
+        scheduler.schedule_frame(Duration::from_secs(10));
+        // Let the thread take the deadline
+        std::thread::sleep(Duration::from_millis(50));
+        scheduler.cancel();
+        scheduler.schedule_frame(Duration::from_millis(10));
+        assert!(rx.recv_timeout(Duration::from_secs(1)).is_ok());
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+}
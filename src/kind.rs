@@ -18,6 +18,9 @@ pub enum Kind {
         subsurface: WlSubsurface,
         surface: WlSurface,
     },
+    /// Plain surface owned by someone else, e.g. a subsurface of another
+    /// library
+    Surface(WlSurface),
 }
 impl Kind {
     fn get_object_id(&self) -> ObjectId {
@@ -26,6 +29,7 @@ impl Kind {
             Kind::LayerSurface(layer_surface) => layer_surface.wl_surface().id(),
             Kind::Popup(popup) => popup.wl_surface().id(),
             Kind::Subsurface { surface, .. } => surface.id(),
+            Kind::Surface(surface) => surface.id(),
         }
     }
 
@@ -35,9 +39,39 @@ impl Kind {
             Kind::LayerSurface(layer_surface) => layer_surface.wl_surface(),
             Kind::Popup(popup) => popup.wl_surface(),
             Kind::Subsurface { surface, .. } => surface,
+            Kind::Surface(surface) => surface,
         }
     }
 
+    pub fn as_window(&self) -> Option<&Window> {
+        match self {
+            Kind::Window(window) => Some(window),
+            _ => None,
+        }
+    }
+
+    pub fn as_layer_surface(&self) -> Option<&LayerSurface> {
+        match self {
+            Kind::LayerSurface(layer_surface) => Some(layer_surface),
+            _ => None,
+        }
+    }
+
+    /// Deterministic name for debugging, e.g. `window@12`
+    ///
+    /// The number is the Wayland protocol id of the surface, the same one
+    /// shown by `WAYLAND_DEBUG=1`.
+    pub fn debug_name(&self) -> String {
+        let prefix = match self {
+            Kind::Window(_) => "window",
+            Kind::LayerSurface(_) => "layer",
+            Kind::Popup(_) => "popup",
+            Kind::Subsurface { .. } => "subsurface",
+            Kind::Surface(_) => "surface",
+        };
+        format!("{}@{}", prefix, self.get_object_id().protocol_id())
+    }
+
     // pub fn is_window(&self, other: &Window) -> bool {
     //     match self {
     //         Kind::Window(_) => self.get_object_id() == other.wl_surface().id(),
@@ -116,6 +150,18 @@ impl From<&Popup> for Kind {
     }
 }
 
+impl From<WlSurface> for Kind {
+    fn from(surface: WlSurface) -> Self {
+        Kind::Surface(surface)
+    }
+}
+
+impl From<&WlSurface> for Kind {
+    fn from(surface: &WlSurface) -> Self {
+        Kind::Surface(surface.clone())
+    }
+}
+
 impl From<(WlSurface, WlSubsurface, WlSurface)> for Kind {
     fn from((parent, subsurface, surface): (WlSurface, WlSubsurface, WlSurface)) -> Self {
         Kind::Subsurface {
@@ -1,13 +1,29 @@
 mod application;
+#[cfg(feature = "calloop")]
+mod calloop_loop;
+mod coordinates;
 // mod egui;
 mod egui;
+mod event_buffer;
 mod frame_scheduler;
 mod kind;
+mod per_output;
+#[cfg(feature = "portal")]
+mod portal;
 mod single_color;
+mod size;
 
 pub use application::*;
+#[cfg(feature = "calloop")]
+pub use calloop_loop::*;
+pub use coordinates::*;
 // pub use egui::*;
 pub use egui::*;
+pub(crate) use event_buffer::*;
 pub(crate) use frame_scheduler::*;
 pub use kind::*;
+pub use per_output::*;
+#[cfg(feature = "portal")]
+pub(crate) use portal::*;
 pub use single_color::*;
+pub use size::*;
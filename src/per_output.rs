@@ -0,0 +1,97 @@
+//! Per-output surfaces
+//!
+//! Keeps one surface per output (monitor), e.g. a status bar or a wallpaper.
+//! Surfaces are created for outputs added later (hotplug) and dropped when
+//! their output is removed.
+
+use crate::Application;
+use crate::WaylandEvent;
+use log::trace;
+use std::collections::HashMap;
+use wayland_backend::client::ObjectId;
+use wayland_client::Proxy;
+use wayland_client::protocol::wl_output::WlOutput;
+
+/// One surface per output, see `Application::create_layer_surface_on`
+pub struct PerOutputSurfaces<S> {
+    surfaces: HashMap<ObjectId, (WlOutput, S)>,
+    create: CreateSurface<S>,
+}
+
+/// Surface constructor of `PerOutputSurfaces::new`
+type CreateSurface<S> = Box<dyn FnMut(&Application, &WlOutput) -> S>;
+
+impl<S> PerOutputSurfaces<S> {
+    /// Create surfaces for all current outputs with `create`
+    ///
+    /// Outputs are usually not known right after `Application::new`, in that
+    /// case the surfaces are created when `OutputCreated` events arrive in
+    /// `handle_events`.
+    pub fn new(
+        app: &Application,
+        create: impl FnMut(&Application, &WlOutput) -> S + 'static,
+    ) -> Self {
+        let mut surfaces = Self {
+            surfaces: HashMap::new(),
+            create: Box::new(create),
+        };
+        for output in app.output_state.outputs() {
+            surfaces.add_output(app, &output);
+        }
+        surfaces
+    }
+
+    fn add_output(&mut self, app: &Application, output: &WlOutput) {
+        if self.surfaces.contains_key(&output.id()) {
+            return;
+        }
+        trace!("[OUTPUT] Creating surface for output {:?}", output.id());
+        let surface = (self.create)(app, output);
+        self.surfaces.insert(output.id(), (output.clone(), surface));
+    }
+
+    fn remove_output(&mut self, output: &WlOutput) {
+        if self.surfaces.remove(&output.id()).is_some() {
+            trace!("[OUTPUT] Dropped surface for output {:?}", output.id());
+        }
+    }
+
+    /// Create and drop surfaces as outputs are added and removed
+    pub fn handle_events(&mut self, app: &Application, events: &[WaylandEvent]) {
+        for event in events {
+            match event {
+                WaylandEvent::OutputCreated(output) => {
+                    self.add_output(app, output);
+                }
+                WaylandEvent::OutputDestroyed(output) => {
+                    self.remove_output(output);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn get(&self, output: &WlOutput) -> Option<&S> {
+        self.surfaces.get(&output.id()).map(|(_, s)| s)
+    }
+
+    pub fn get_mut(&mut self, output: &WlOutput) -> Option<&mut S> {
+        self.surfaces.get_mut(&output.id()).map(|(_, s)| s)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&WlOutput, &S)> {
+        self.surfaces.values().map(|(o, s)| (o, s))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&WlOutput, &mut S)> {
+        self.surfaces.values_mut().map(|(o, s)| (&*o, s))
+    }
+
+    pub fn len(&self) -> usize {
+        self.surfaces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.surfaces.is_empty()
+    }
+}
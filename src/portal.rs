@@ -0,0 +1,98 @@
+//! File dialogs and opening URLs via xdg-desktop-portal
+//!
+//! The portal shows the desktop's native dialogs and works inside sandboxes
+//! like Flatpak. Requests run on their own thread, results are sent with
+//! the surface's `channel` which schedules a frame, so they can be received
+//! with `try_recv` in the next `ui()` pass.
+
+use crate::EguiSurfaceState;
+use crate::Kind;
+use crate::SurfaceSender;
+use ashpd::WindowIdentifier;
+use ashpd::desktop::file_chooser::SelectedFiles;
+use ashpd::desktop::open_uri::OpenFileRequest;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+
+impl<T: Into<Kind> + Clone> EguiSurfaceState<T> {
+    /// Ask the user for a file to open
+    ///
+    /// The dialog is modal to this surface if the compositor supports
+    /// xdg-foreign. Receives `None` if the dialog was cancelled or the portal
+    /// isn't available.
+    pub fn open_file_dialog(&self, title: &str) -> Receiver<Option<PathBuf>> {
+        let title = title.to_string();
+        let parent = self.wl_surface().clone();
+        spawn_request(self.channel(), async move {
+            // The parent is exported with xdg-foreign, without it the dialog
+            // has no parent to be modal to
+            let identifier = WindowIdentifier::from_wayland(&parent).await;
+            SelectedFiles::open_file()
+                .title(title.as_str())
+                .modal(identifier.is_some())
+                .identifier(identifier)
+                .multiple(false)
+                .send()
+                .await?
+                .response()
+        })
+    }
+
+    /// Ask the user where to save a file, `current_name` is the suggested
+    /// file name
+    ///
+    /// Modal like `open_file_dialog`. Receives `None` if the dialog was
+    /// cancelled or the portal isn't available.
+    pub fn save_file_dialog(&self, title: &str, current_name: &str) -> Receiver<Option<PathBuf>> {
+        let title = title.to_string();
+        let current_name = current_name.to_string();
+        let parent = self.wl_surface().clone();
+        spawn_request(self.channel(), async move {
+            let identifier = WindowIdentifier::from_wayland(&parent).await;
+            SelectedFiles::save_file()
+                .title(title.as_str())
+                .current_name(current_name.as_str())
+                .modal(identifier.is_some())
+                .identifier(identifier)
+                .send()
+                .await?
+                .response()
+        })
+    }
+}
+
+/// Run a file chooser request on its own thread and send the first chosen
+/// path
+fn spawn_request(
+    (sender, receiver): (SurfaceSender<Option<PathBuf>>, Receiver<Option<PathBuf>>),
+    request: impl Future<Output = ashpd::Result<SelectedFiles>> + Send + 'static,
+) -> Receiver<Option<PathBuf>> {
+    std::thread::spawn(move || {
+        let path = match futures::executor::block_on(request) {
+            Ok(files) => files.uris().first().and_then(|uri| uri.to_file_path().ok()),
+            Err(err) => {
+                log::warn!("[PORTAL] File chooser failed: {}", err);
+                None
+            }
+        };
+        let _ = sender.send(path);
+    });
+    receiver
+}
+
+/// Open a URL in the default application, e.g. a link in the browser
+pub(crate) fn open_url(url: &str) {
+    let uri = match ashpd::url::Url::parse(url) {
+        Ok(uri) => uri,
+        Err(err) => {
+            log::warn!("[PORTAL] Invalid URL {}: {}", url, err);
+            return;
+        }
+    };
+    std::thread::spawn(move || {
+        let request = OpenFileRequest::default().send_uri(&uri);
+        if let Err(err) = futures::executor::block_on(request) {
+            log::warn!("[PORTAL] Failed to open URL {}: {}", uri, err);
+        }
+    });
+}
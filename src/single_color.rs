@@ -3,14 +3,13 @@
 ///! Use this as an example to how to start implementing your own containers.
 use crate::Application;
 use crate::Kind;
+use crate::LogicalSize;
 use crate::WaylandEvent;
 use log::trace;
 use smithay_client_toolkit::shm::slot::SlotPool;
 use std::num::NonZero;
 use std::ops::Deref;
 use std::ops::DerefMut;
-use std::time::Duration;
-use std::time::Instant;
 use wayland_client::Proxy;
 use wayland_client::QueueHandle;
 use wayland_client::protocol::wl_shm;
@@ -24,9 +23,10 @@ pub struct SingleColorState<T: Into<Kind> + Clone> {
     slotpool: Option<SlotPool>,
     viewport: Option<WpViewport>,
     color: (u8, u8, u8),
-    last_buffer_update: Option<Instant>,
     init_width: u32,
     init_height: u32,
+    size: Option<(u32, u32)>,
+    scale_factor: i32,
 }
 
 impl<T: Into<Kind> + Clone> SingleColorState<T> {
@@ -37,12 +37,19 @@ impl<T: Into<Kind> + Clone> SingleColorState<T> {
             slotpool: None,
             viewport: None,
             color,
-            last_buffer_update: None,
             init_width: width,
             init_height: height,
+            size: None,
+            scale_factor: 1,
         }
     }
 
+    /// Set the logical size of a surface that gets no configure events, e.g.
+    /// a subsurface
+    pub fn set_size(&mut self, app: &Application, width: u32, height: u32) {
+        self.configure(app, LogicalSize::new(width, height));
+    }
+
     pub fn wl_surface(&self) -> &WlSurface {
         self.kind.get_wl_surface()
     }
@@ -62,12 +69,26 @@ impl<T: Into<Kind> + Clone> SingleColorState<T> {
                 .get_viewport(&surface, &app.qh, ())
         });
 
-        viewport.set_destination(width as i32, height as i32);
+        // Zero destination is a protocol error
+        viewport.set_destination(width.max(1) as i32, height.max(1) as i32);
     }
 
     fn update_buffers(&mut self, app: &Application, width: u32, height: u32) {
         let surface = self.wl_surface().clone();
         let viewport = self.viewport.as_ref().expect("Viewport should exist");
+
+        // The buffer is in physical pixels, the viewport scales it to the
+        // logical size, so it stays sharp on HiDPI (also for subsurfaces)
+        let scale = self.scale_factor.max(1) as u32;
+        let (width, height) = (width * scale, height * scale);
+        trace!(
+            "[SINGLE_COLOR] Physical size of surface {:?}: {}x{} (scale {})",
+            surface.id(),
+            width,
+            height,
+            scale
+        );
+
         let pool = self.slotpool.get_or_insert_with(|| {
             trace!("[SINGLE_COLOR] Creating buffer pool");
             SlotPool::new((width * height * 4).try_into().unwrap(), &app.shm_state)
@@ -79,71 +100,79 @@ impl<T: Into<Kind> + Clone> SingleColorState<T> {
         );
     }
 
-    fn configure(&mut self, app: &Application, width: u32, height: u32) {
+    fn scale_factor_changed(&mut self, app: &Application, factor: i32) {
+        let factor = factor.max(1);
+        if factor == self.scale_factor {
+            return;
+        }
+        self.scale_factor = factor;
+        if let Some((width, height)) = self.size {
+            self.update_buffers(app, width, height);
+        }
+    }
+
+    /// Size of the last configure, or the initial size before the first
+    fn current_size(&self) -> LogicalSize {
+        LogicalSize::from(self.size.unwrap_or((self.init_width, self.init_height)))
+    }
+
+    fn configure(&mut self, app: &Application, size: LogicalSize) {
+        let LogicalSize { width, height } = size.at_least_one();
         trace!(
             "[SINGLE_COLOR] Configure received for surface {:?}: {}x{}",
             self.wl_surface().id(),
             width,
             height
         );
-        const DEBOUNCE_MS: u64 = 32; // ~30fps, adjust as needed
-        let surface = self.wl_surface().clone();
 
-        let now = Instant::now();
-
-        // Always resize viewport (fast operation)
+        // Superseded configures are skipped in `handle_events` instead of
+        // debouncing by time, which left the last configure of a fast resize
+        // showing the stale buffer stretched to the new size
         self.resize_viewport(app, width, height);
-
-        // Check if we should update buffers (debounced)
-        let should_update_buffer = if let Some(last_time) = self.last_buffer_update {
-            now.duration_since(last_time) >= Duration::from_millis(DEBOUNCE_MS)
-        } else {
-            true // First configure, always update
-        };
-
-        if should_update_buffer {
-            // Update buffers (slow operation)
-            self.update_buffers(app, width, height);
-            // TODO: BUG, this is not called when configures come too fast
-        } else {
-            // Just commit the surface with the new viewport destination
-            surface.commit();
-        }
-
-        // Always update the timestamp to reset the debounce timer
-        self.last_buffer_update = Some(now);
+        self.size = Some((width, height));
+        self.update_buffers(app, width, height);
     }
 
     pub fn handle_events(&mut self, app: &Application, events: &[WaylandEvent]) {
-        for event in events {
+        // Only the latest configure of a batch needs a buffer, SCTK has acked
+        // the earlier ones already
+        let last_configure = events.iter().rposition(|event| {
+            event.is_configure()
+                && event
+                    .get_wl_surface()
+                    .is_some_and(|surface| surface.id() == self.wl_surface().id())
+        });
+
+        for (index, event) in events.iter().enumerate() {
             if let Some(surface) = event.get_wl_surface() {
                 if surface.id() != self.wl_surface().id() {
                     continue;
                 }
             }
+            if event.is_configure() && Some(index) != last_configure {
+                continue;
+            }
             match event {
                 WaylandEvent::WindowConfigure(_, configure) => {
-                    let width = configure
-                        .new_size
-                        .0
-                        .unwrap_or_else(|| NonZero::new(self.init_width).unwrap())
-                        .get();
-                    let height = configure
-                        .new_size
-                        .1
-                        .unwrap_or_else(|| NonZero::new(self.init_height).unwrap())
-                        .get();
-                    self.configure(app, width, height);
+                    // None means the client decides, keep the current size
+                    let size = LogicalSize::new(
+                        configure.new_size.0.map_or(0, NonZero::get),
+                        configure.new_size.1.map_or(0, NonZero::get),
+                    );
+                    self.configure(app, size.or_current(self.current_size()));
                 }
                 WaylandEvent::LayerShellConfigure(_, config) => {
-                    let width = config.new_size.0;
-                    let height = config.new_size.1;
-                    self.configure(app, width, height);
+                    // Zero means the client decides, keep the current size
+                    let size = LogicalSize::from(config.new_size);
+                    self.configure(app, size.or_current(self.current_size()));
                 }
                 WaylandEvent::PopupConfigure(_, config) => {
-                    let width = config.width as u32;
-                    let height = config.height as u32;
-                    self.configure(app, width, height);
+                    let size =
+                        LogicalSize::new(config.width.max(0) as u32, config.height.max(0) as u32);
+                    self.configure(app, size.or_current(self.current_size()));
+                }
+                WaylandEvent::ScaleFactorChanged(_, factor) => {
+                    self.scale_factor_changed(app, *factor);
                 }
                 _ => {}
             }
@@ -176,13 +205,7 @@ fn single_color_example_buffer_configure(
         )
         .expect("create buffer");
     if let Some(canvas) = pool.canvas(&buffer) {
-        for chunk in canvas.chunks_exact_mut(4) {
-            // ARGB little-endian: B, G, R, A
-            chunk[0] = color.2; // B
-            chunk[1] = color.1; // G
-            chunk[2] = color.0; // R
-            chunk[3] = 0xFF; // A
-        }
+        fill_argb(canvas, color);
     }
 
     // Set the source rectangle to the entire buffer
@@ -195,6 +218,18 @@ fn single_color_example_buffer_configure(
     surface.commit();
 }
 
+/// Fill a `wl_shm::Format::Argb8888` canvas with an opaque color
+///
+/// Wayland SHM formats are little-endian regardless of the host, so each pixel
+/// is stored as the bytes B, G, R, A.
+pub fn fill_argb(canvas: &mut [u8], color: (u8, u8, u8)) {
+    let (r, g, b) = color;
+    let pixel = u32::from_be_bytes([0xFF, r, g, b]).to_le_bytes();
+    for chunk in canvas.chunks_exact_mut(4) {
+        chunk.copy_from_slice(&pixel);
+    }
+}
+
 impl<T: Into<Kind> + Clone> Deref for SingleColorState<T> {
     type Target = T;
 
@@ -208,3 +243,36 @@ impl<T: Into<Kind> + Clone> DerefMut for SingleColorState<T> {
         &mut self.t
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_configure_keeps_the_initial_size() {
+        let current = LogicalSize::new(300, 200);
+        assert_eq!(LogicalSize::new(0, 0).or_current(current), current);
+        assert_eq!(
+            LogicalSize::new(640, 0).or_current(current),
+            LogicalSize::new(640, 200)
+        );
+        assert_eq!(
+            LogicalSize::new(0, 32).or_current(current),
+            LogicalSize::new(300, 32)
+        );
+        assert_eq!(
+            LogicalSize::new(1, 2).or_current(current),
+            LogicalSize::new(1, 2)
+        );
+        // Even a zero initial size never reaches the viewport
+        let size = LogicalSize::new(0, 0).or_current(LogicalSize::default());
+        assert_eq!(size.at_least_one(), LogicalSize::new(1, 1));
+    }
+
+    #[test]
+    fn fill_argb_is_little_endian() {
+        let mut canvas = [0; 8];
+        fill_argb(&mut canvas, (0x11, 0x22, 0x33));
+        assert_eq!(canvas, [0x33, 0x22, 0x11, 0xFF, 0x33, 0x22, 0x11, 0xFF]);
+    }
+}
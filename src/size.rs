@@ -0,0 +1,105 @@
+//! Logical and physical sizes
+//!
+//! Logical pixels are the surface-local coordinates Wayland uses for surface
+//! sizes and pointer positions, they are the same as EGUI points. Physical
+//! pixels are buffer pixels. `Scale` converts between the two, so mixing them
+//! up is a type error instead of a HiDPI bug.
+
+/// Size in logical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LogicalSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Size in physical (buffer) pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PhysicalSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Physical pixels per logical pixel, always positive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scale(f32);
+
+impl LogicalSize {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    /// Clamp to at least 1x1, zero sized buffers and viewport destinations
+    /// are protocol errors
+    pub fn at_least_one(self) -> Self {
+        Self::new(self.width.max(1), self.height.max(1))
+    }
+
+    /// Size of a configure, zero axes are left to the client and keep the
+    /// `current` size
+    pub fn or_current(self, current: LogicalSize) -> Self {
+        Self::new(
+            match self.width {
+                0 => current.width,
+                width => width,
+            },
+            match self.height {
+                0 => current.height,
+                height => height,
+            },
+        )
+    }
+
+    pub fn to_physical(self, scale: Scale) -> PhysicalSize {
+        PhysicalSize::new(
+            (self.width as f32 * scale.get()).round() as u32,
+            (self.height as f32 * scale.get()).round() as u32,
+        )
+    }
+}
+
+impl PhysicalSize {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    /// Clamp to at least 1x1, zero sized swapchains are invalid
+    pub fn at_least_one(self) -> Self {
+        Self::new(self.width.max(1), self.height.max(1))
+    }
+
+    pub fn to_logical(self, scale: Scale) -> LogicalSize {
+        LogicalSize::new(
+            (self.width as f32 / scale.get()).round() as u32,
+            (self.height as f32 / scale.get()).round() as u32,
+        )
+    }
+}
+
+impl Scale {
+    pub const ONE: Scale = Scale(1.0);
+
+    /// Non-positive and non-finite scales fall back to `1.0`
+    pub fn new(scale: f32) -> Self {
+        if scale.is_finite() && scale > 0.0 {
+            Self(scale)
+        } else {
+            Self::ONE
+        }
+    }
+
+    pub fn get(self) -> f32 {
+        self.0
+    }
+}
+
+impl Default for Scale {
+    fn default() -> Self {
+        Self::ONE
+    }
+}
+
+impl From<(u32, u32)> for LogicalSize {
+    fn from((width, height): (u32, u32)) -> Self {
+        Self::new(width, height)
+    }
+}